@@ -0,0 +1,229 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Audit policies, listing the key properties which `inventory` reports as findings.
+//!
+//! A policy is a list of rules, read from a TOML file. A rule matches a key when all its conditions
+//! match; rules without conditions match every key:
+//!
+//! ```toml
+//! [[rule]]
+//! id = "rsa-key-size"
+//! severity = "medium"
+//! description = "RSA key shorter than 3072 bits"
+//! key-types = ["rsa-key-pair", "rsa-public-key"]
+//! bits-below = 3072
+//!
+//! [[rule]]
+//! id = "sha224"
+//! severity = "low"
+//! description = "Algorithm using SHA-224"
+//! hash = "sha224"
+//! ```
+//!
+//! The conditions are:
+//! * `key-types`: the key type is one of these, written as for `create-key --type`; a type without
+//!   curve family matches all the families,
+//! * `bits-below`: the key is shorter than this,
+//! * `algorithms`: the permitted algorithm is one of these, written as for `create-key --algorithm`;
+//!   an algorithm without parameters matches all the parameters, e.g. `rsa-oaep` for `rsa-oaep(sha256)`,
+//! * `hash`: the permitted algorithm uses this hash algorithm, written as for `parse_hash` (e.g. `sha256`
+//!   or `SHA-256`),
+//! * `exportable`: the key can, or can not, be exported.
+//!
+//! The default policy, used when no file is given, contains the rules of the example above, and
+//! reports PKCS#1 v1.5 encryption (high) and exportable key pairs (low).
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_spec::{algorithm_to_string, hash_to_str, key_type_to_string, parse_hash};
+use log::error;
+use parsec_client::core::interface::operations::psa_key_attributes::Attributes;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Severity of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    /// Informative finding.
+    Low,
+    /// Weakness to fix.
+    Medium,
+    /// Weakness to fix urgently.
+    High,
+    /// Key which should not be used anymore.
+    Critical,
+}
+
+impl Severity {
+    /// Returns the name of the severity, as accepted by `from_str`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!(
+                "unknown severity \"{}\", expected low, medium, high or critical",
+                input
+            )),
+        }
+    }
+}
+
+/// Rules of an audit policy.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AuditPolicy {
+    #[serde(default, rename = "rule")]
+    rules: Vec<AuditRule>,
+}
+
+/// A rule of an audit policy.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AuditRule {
+    /// Short identifier of the rule.
+    pub id: String,
+    /// Severity of the findings of the rule.
+    pub severity: Severity,
+    /// Description of the findings of the rule.
+    pub description: String,
+    #[serde(default)]
+    key_types: Vec<String>,
+    bits_below: Option<usize>,
+    #[serde(default)]
+    algorithms: Vec<String>,
+    hash: Option<String>,
+    exportable: Option<bool>,
+}
+
+impl AuditRule {
+    fn new(id: &str, severity: Severity, description: &str) -> Self {
+        AuditRule {
+            id: id.to_string(),
+            severity,
+            description: description.to_string(),
+            key_types: Vec::new(),
+            bits_below: None,
+            algorithms: Vec::new(),
+            hash: None,
+            exportable: None,
+        }
+    }
+
+    /// Returns true if the rule matches a key.
+    pub fn matches(&self, attributes: &Attributes) -> bool {
+        let key_type = key_type_to_string(attributes.key_type);
+        let algorithm = algorithm_to_string(attributes.policy.permitted_algorithms);
+
+        (self.key_types.is_empty()
+            || self
+                .key_types
+                .iter()
+                .any(|expected| matches_call(&key_type, expected)))
+            && self.bits_below.map_or(true, |bits| attributes.bits < bits)
+            && (self.algorithms.is_empty()
+                || self
+                    .algorithms
+                    .iter()
+                    .any(|expected| matches_call(&algorithm, expected)))
+            && self.hash.as_deref().map_or(true, |hash| {
+                algorithm
+                    .split(&['(', ')', ','][..])
+                    .any(|token| token.trim() == hash)
+            })
+            && self
+                .exportable
+                .map_or(true, |exportable| attributes.is_exportable() == exportable)
+    }
+}
+
+// Checks if a string in the function-like syntax of key types and algorithms is `expected`, or has the
+// name of `expected` when `expected` has no parameters.
+fn matches_call(actual: &str, expected: &str) -> bool {
+    actual == expected || (!expected.contains('(') && actual.split('(').next() == Some(expected))
+}
+
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        let mut rsa_key_size = AuditRule::new(
+            "rsa-key-size",
+            Severity::Medium,
+            "RSA key shorter than 3072 bits",
+        );
+        rsa_key_size.key_types = vec![String::from("rsa-key-pair"), String::from("rsa-public-key")];
+        rsa_key_size.bits_below = Some(3072);
+
+        let mut pkcs1v15_encryption = AuditRule::new(
+            "rsa-pkcs1v15-encryption",
+            Severity::High,
+            "RSA PKCS#1 v1.5 encryption",
+        );
+        pkcs1v15_encryption.algorithms = vec![String::from("rsa-pkcs1v15-crypt")];
+
+        let mut sha224 = AuditRule::new("sha224", Severity::Low, "Algorithm using SHA-224");
+        sha224.hash = Some(String::from("sha224"));
+
+        let mut exportable_key_pair = AuditRule::new(
+            "exportable-key-pair",
+            Severity::Low,
+            "Private key which can be exported",
+        );
+        exportable_key_pair.key_types = vec![
+            String::from("rsa-key-pair"),
+            String::from("ecc-key-pair"),
+            String::from("dh-key-pair"),
+        ];
+        exportable_key_pair.exportable = Some(true);
+
+        AuditPolicy {
+            rules: vec![
+                rsa_key_size,
+                pkcs1v15_encryption,
+                sha224,
+                exportable_key_pair,
+            ],
+        }
+    }
+}
+
+impl AuditPolicy {
+    /// Reads a policy from a TOML file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let mut policy: AuditPolicy =
+            toml::from_str(&std::fs::read_to_string(path)?).map_err(|e| {
+                error!("Could not parse the audit policy: {}", e);
+                ToolErrorKind::IncorrectData
+            })?;
+        // Hash algorithms are matched in the syntax of `algorithm_to_string`, e.g. "sha256" for "SHA-256".
+        for rule in &mut policy.rules {
+            if let Some(hash) = &rule.hash {
+                rule.hash = Some(hash_to_str(parse_hash(hash)?).to_string());
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Returns the rules matching a key.
+    pub fn findings(&self, attributes: &Attributes) -> Vec<&AuditRule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(attributes))
+            .collect()
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Self-describing container for symmetric ciphertexts.
+//!
+//! Data encrypted with a symmetric key is by default output in the following structure, so that it
+//! can be decrypted knowing only the name of the key (and the additional data, if any was
+//! authenticated):
+//!
+//! | Field        | Size            |
+//! |--------------|-----------------|
+//! | version      | 1 byte          |
+//! | algorithm    | 1 byte          |
+//! | nonce length | 1 byte          |
+//! | nonce        | nonce length    |
+//! | tag length   | 1 byte          |
+//! | tag          | tag length      |
+//! | ciphertext   | remaining bytes |
+//!
+//! The version is currently 1. The algorithm is 1 for CCM, 2 for GCM and 3 for ChaCha20-Poly1305
+//! (AEAD algorithms), 16 for the ChaCha20 stream cipher, 17 for CTR, 18 for CFB, 19 for OFB, 20 for
+//! XTS, 21 for ECB, 22 for CBC without padding and 23 for CBC with PKCS#7 padding (unauthenticated
+//! cipher modes, for which the nonce is the IV and the tag is empty).
+//!
+//! For interoperability with other systems, data encrypted with a cipher mode can instead use the
+//! convention of the Parsec service, where the IV is prepended to the ciphertext, or have the IV
+//! transmitted separately (see `CipherFormat`).
+
+use crate::error::{Result, ToolErrorKind};
+use log::error;
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, Cipher,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::Type;
+use std::convert::TryInto;
+use std::str::FromStr;
+
+const VERSION: u8 = 1;
+
+// Length of the authentication tag of all the supported AEAD algorithms, unless shortened.
+const DEFAULT_TAG_LENGTH: usize = 16;
+
+/// Length of the nonces generated for AEAD encryption. It is valid for all the supported algorithms.
+pub const AEAD_NONCE_LENGTH: usize = 12;
+
+/// Layout of the data encrypted with an unauthenticated cipher mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherFormat {
+    /// Ciphertext container described in this module (`container`).
+    Container,
+    /// IV followed by the ciphertext, as used by the Parsec service (`iv-prepended`).
+    IvPrepended,
+    /// IV and ciphertext kept apart (`iv-separate`).
+    IvSeparate,
+}
+
+impl FromStr for CipherFormat {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "container" => Ok(CipherFormat::Container),
+            "iv-prepended" => Ok(CipherFormat::IvPrepended),
+            "iv-separate" => Ok(CipherFormat::IvSeparate),
+            _ => Err(format!(
+                "\"{}\" is not one of container, iv-prepended or iv-separate",
+                input
+            )),
+        }
+    }
+}
+
+/// Decoded symmetric ciphertext container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+    /// Algorithm used for encryption.
+    pub algorithm: Algorithm,
+    /// Nonce used for encryption.
+    pub nonce: Vec<u8>,
+    /// Authentication tag.
+    pub tag: Vec<u8>,
+    /// Encrypted data, without the tag.
+    pub ciphertext: Vec<u8>,
+}
+
+impl Ciphertext {
+    /// Creates a container from the output of `psa_aead_encrypt`, which is the ciphertext followed by the
+    /// tag.
+    pub fn from_aead_output(alg: Aead, nonce: Vec<u8>, mut output: Vec<u8>) -> Result<Self> {
+        let tag_length = aead_tag_length(alg);
+        if output.len() < tag_length {
+            error!("The AEAD output is shorter than its tag");
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+        let tag = output.split_off(output.len() - tag_length);
+        Ok(Ciphertext {
+            algorithm: alg.into(),
+            nonce,
+            tag,
+            ciphertext: output,
+        })
+    }
+
+    /// Creates a container from the output of `psa_cipher_encrypt`, which is the IV followed by the
+    /// ciphertext.
+    pub fn from_cipher_output(alg: Cipher, key_type: Type, mut output: Vec<u8>) -> Result<Self> {
+        let iv_length = cipher_iv_length(alg, key_type)?;
+        if output.len() < iv_length {
+            error!("The cipher output is shorter than its IV");
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+        let ciphertext = output.split_off(iv_length);
+        Ok(Ciphertext {
+            algorithm: alg.into(),
+            nonce: output,
+            tag: Vec::new(),
+            ciphertext,
+        })
+    }
+
+    /// Returns the input expected by `psa_cipher_decrypt`: the IV followed by the ciphertext.
+    pub fn cipher_input(&self) -> Vec<u8> {
+        let mut input = self.nonce.clone();
+        input.extend_from_slice(&self.ciphertext);
+        input
+    }
+
+    /// Returns the input expected by `psa_aead_decrypt`: the ciphertext followed by the tag.
+    pub fn aead_input(&self) -> Vec<u8> {
+        let mut input = self.ciphertext.clone();
+        input.extend_from_slice(&self.tag);
+        input
+    }
+
+    /// Serialises the container.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let algorithm = match self.algorithm {
+            Algorithm::Aead(alg) => aead_id(alg),
+            Algorithm::Cipher(alg) => cipher_id(alg),
+            other => {
+                error!("Algorithm {:?} can not be stored in a ciphertext", other);
+                return Err(ToolErrorKind::NotSupported.into());
+            }
+        };
+        let mut bytes = vec![VERSION, algorithm];
+        for field in [&self.nonce, &self.tag].iter() {
+            bytes.push(field.len().try_into().map_err(|_| {
+                error!("Nonce or tag is too long to be stored in a ciphertext");
+                ToolErrorKind::IncorrectData
+            })?);
+            bytes.extend_from_slice(field);
+        }
+        bytes.extend_from_slice(&self.ciphertext);
+        Ok(bytes)
+    }
+
+    /// Deserialises a container.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (version, bytes) = split_byte(bytes)?;
+        if version != VERSION {
+            error!("Unsupported ciphertext version {}", version);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+        let (algorithm, bytes) = split_byte(bytes)?;
+        let (nonce, bytes) = split_field(bytes)?;
+        let (tag, ciphertext) = split_field(bytes)?;
+        let algorithm = if algorithm < CIPHER_ID_BASE {
+            aead_from_id(algorithm, tag.len())?.into()
+        } else if tag.is_empty() {
+            cipher_from_id(algorithm)?.into()
+        } else {
+            error!("Unexpected tag in a ciphertext of an unauthenticated cipher mode");
+            return Err(ToolErrorKind::IncorrectData.into());
+        };
+
+        Ok(Ciphertext {
+            algorithm,
+            nonce: nonce.to_vec(),
+            tag: tag.to_vec(),
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
+}
+
+/// Returns the length of the authentication tag of an AEAD algorithm.
+pub fn aead_tag_length(alg: Aead) -> usize {
+    match alg {
+        Aead::AeadWithDefaultLengthTag(_) => DEFAULT_TAG_LENGTH,
+        Aead::AeadWithShortenedTag { tag_length, .. } => tag_length,
+    }
+}
+
+/// Returns the length of the IV prepended by the Parsec service to data encrypted with a cipher mode.
+pub fn cipher_iv_length(alg: Cipher, key_type: Type) -> Result<usize> {
+    let block_size = match key_type {
+        Type::Aes | Type::Camellia => 16,
+        Type::Des => 8,
+        Type::Chacha20 if alg == Cipher::StreamCipher => return Ok(12),
+        _ => {
+            error!("{:?} can not be used with {} keys", alg, key_type);
+            return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+        }
+    };
+    match alg {
+        Cipher::EcbNoPadding => Ok(0),
+        Cipher::StreamCipher => {
+            error!("The stream cipher mode can only be used with ChaCha20 keys");
+            Err(ToolErrorKind::WrongKeyAlgorithm.into())
+        }
+        _ => Ok(block_size),
+    }
+}
+
+fn aead_id(alg: Aead) -> u8 {
+    let aead_alg = match alg {
+        Aead::AeadWithDefaultLengthTag(aead_alg) => aead_alg,
+        Aead::AeadWithShortenedTag { aead_alg, .. } => aead_alg,
+    };
+    match aead_alg {
+        AeadWithDefaultLengthTag::Ccm => 1,
+        AeadWithDefaultLengthTag::Gcm => 2,
+        AeadWithDefaultLengthTag::Chacha20Poly1305 => 3,
+    }
+}
+
+fn aead_from_id(id: u8, tag_length: usize) -> Result<Aead> {
+    let aead_alg = match id {
+        1 => AeadWithDefaultLengthTag::Ccm,
+        2 => AeadWithDefaultLengthTag::Gcm,
+        3 => AeadWithDefaultLengthTag::Chacha20Poly1305,
+        _ => {
+            error!("Unknown ciphertext algorithm {}", id);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+    if tag_length == DEFAULT_TAG_LENGTH {
+        Ok(Aead::AeadWithDefaultLengthTag(aead_alg))
+    } else {
+        Ok(Aead::AeadWithShortenedTag {
+            aead_alg,
+            tag_length,
+        })
+    }
+}
+
+// Algorithm identifiers from this value on are cipher modes.
+const CIPHER_ID_BASE: u8 = 16;
+
+fn cipher_id(alg: Cipher) -> u8 {
+    match alg {
+        Cipher::StreamCipher => 16,
+        Cipher::Ctr => 17,
+        Cipher::Cfb => 18,
+        Cipher::Ofb => 19,
+        Cipher::Xts => 20,
+        Cipher::EcbNoPadding => 21,
+        Cipher::CbcNoPadding => 22,
+        Cipher::CbcPkcs7 => 23,
+    }
+}
+
+fn cipher_from_id(id: u8) -> Result<Cipher> {
+    let alg = match id {
+        16 => Cipher::StreamCipher,
+        17 => Cipher::Ctr,
+        18 => Cipher::Cfb,
+        19 => Cipher::Ofb,
+        20 => Cipher::Xts,
+        21 => Cipher::EcbNoPadding,
+        22 => Cipher::CbcNoPadding,
+        23 => Cipher::CbcPkcs7,
+        _ => {
+            error!("Unknown ciphertext algorithm {}", id);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+    Ok(alg)
+}
+
+fn split_byte(bytes: &[u8]) -> Result<(u8, &[u8])> {
+    match bytes.split_first() {
+        Some((byte, rest)) => Ok((*byte, rest)),
+        None => {
+            error!("The ciphertext is truncated");
+            Err(ToolErrorKind::IncorrectData.into())
+        }
+    }
+}
+
+fn split_field(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+    let (length, bytes) = split_byte(bytes)?;
+    let length = usize::from(length);
+    if bytes.len() < length {
+        error!("The ciphertext is truncated");
+        return Err(ToolErrorKind::IncorrectData.into());
+    }
+    Ok(bytes.split_at(length))
+}
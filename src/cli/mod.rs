@@ -4,7 +4,12 @@
 //! Base CLI implementation.
 
 use crate::common::{PROJECT_AUTHOR, PROJECT_DESC, PROJECT_NAME, PROJECT_VERSION};
+use crate::error::{Result, ToolErrorKind};
+use crate::guardrail_policy::GuardrailPolicy;
 use crate::subcommands::Subcommand;
+use log::error;
+use parsec_client::BasicClient;
+use std::convert::TryInto;
 use structopt::StructOpt;
 
 /// Struct representing the command-line interface of parsec-tool.
@@ -24,3 +29,59 @@ pub struct ParsecToolApp {
     #[structopt(subcommand)]
     pub subcommand: Subcommand,
 }
+
+impl ParsecToolApp {
+    /// Runs the subcommand.
+    pub fn run(&self) -> Result<()> {
+        match &self.subcommand {
+            Subcommand::Batch(batch) => batch.run(self),
+            // The failures of key-exists, such as an unreachable service, must not be mistaken for an
+            // absent key.
+            subcommand @ Subcommand::KeyExists(key_exists) => self
+                .run_subcommand(subcommand)
+                .map_err(|e| key_exists.check_failed(e)),
+            subcommand => self.run_subcommand(subcommand),
+        }
+    }
+
+    fn run_subcommand(&self, subcommand: &Subcommand) -> Result<()> {
+        let policy = GuardrailPolicy::load()?;
+        let client = self.create_client(subcommand)?;
+        if subcommand.targets_provider() {
+            policy.check_provider(client.implicit_provider())?;
+        }
+        subcommand.run(client)
+    }
+
+    /// Creates the client used to run a subcommand, configured with the global options.
+    pub fn create_client(&self, subcommand: &Subcommand) -> Result<BasicClient> {
+        let mut client = subcommand
+            .create_client(Some(PROJECT_NAME.to_string()))
+            .map_err(|e| {
+                error!("Error spinning up the BasicClient: {}", e);
+                e
+            })?;
+
+        if let Some(provider) = self.provider {
+            let provider = provider.try_into().map_err(|_| {
+                error!("The provider ID entered does not map with an existing provider");
+                ToolErrorKind::IncorrectData
+            })?;
+            client.set_implicit_provider(provider);
+        }
+        if let Some(provider) = subcommand.key_provider() {
+            client.set_implicit_provider(provider);
+        }
+
+        if let Some(timeout) = self.timeout {
+            let timeout = if timeout == 0 {
+                None
+            } else {
+                Some(std::time::Duration::from_secs(timeout.into()))
+            };
+            client.set_timeout(timeout);
+        }
+
+        Ok(client)
+    }
+}
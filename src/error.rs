@@ -51,6 +51,57 @@ pub enum ToolErrorKind {
     /// Cannot serialise or deserialise data
     #[error("Incorrect data format")]
     IncorrectData,
+
+    /// The key's policy does not allow it to be exported
+    #[error("The key's policy does not allow it to be exported")]
+    NotExportable,
+
+    /// Keys have audit findings at or above the severity threshold
+    #[error("Keys have audit findings at or above the severity threshold")]
+    AuditFindings,
+
+    /// The key does not exist
+    #[error("The key does not exist")]
+    KeyDoesNotExist,
+
+    /// The existence of the key could not be checked
+    #[error("The existence of the key could not be checked")]
+    KeyCheckFailed,
+
+    /// The key exists but does not have the expected attributes
+    #[error("The key does not have the expected attributes")]
+    KeyMismatch,
+
+    /// The operation is forbidden by the guardrail policy
+    #[error("The operation is forbidden by the guardrail policy")]
+    PolicyViolation,
+
+    /// The operation was not confirmed
+    #[error("The operation was not confirmed")]
+    NotConfirmed,
+}
+
+impl Error {
+    /// Returns the exit code of parsec-tool for this error: 2 for a key which does not have the expected
+    /// attributes, 3 for audit findings above the threshold, 4 for a key whose existence could not be
+    /// checked, 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::ParsecToolError(ToolErrorKind::KeyMismatch) => 2,
+            Error::ParsecToolError(ToolErrorKind::AuditFindings) => 3,
+            Error::ParsecToolError(ToolErrorKind::KeyCheckFailed) => 4,
+            _ => 1,
+        }
+    }
+
+    /// Returns true if the error is an expected outcome of a check, which should not be logged.
+    pub fn is_check_result(&self) -> bool {
+        matches!(
+            self,
+            Error::ParsecToolError(ToolErrorKind::KeyDoesNotExist)
+                | Error::ParsecToolError(ToolErrorKind::KeyMismatch)
+        )
+    }
 }
 
 /// A Result type with the Err variant set as a ParsecToolError
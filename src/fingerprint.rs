@@ -0,0 +1,212 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fingerprints of public keys.
+//!
+//! The following fingerprints are computed from the public key exported by the Parsec service:
+//! * the SHA-256 hash of the DER SubjectPublicKeyInfo, in hexadecimal, as used for certificate pinning
+//!   and in DANE TLSA records with the "3 1 1" parameters (RFC 7671),
+//! * the JWK thumbprint (RFC 7638) with SHA-256, for RSA keys, NIST P-256, P-384 and P-521 keys and
+//!   X25519 and X448 keys (RFC 8037),
+//! * the OpenSSH SHA-256 fingerprint, also used in SSHFP records (RFC 6594), for RSA keys and NIST
+//!   P-256, P-384 and P-521 keys.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_format::encode_spki;
+use log::error;
+use parsec_client::core::interface::operations::psa_key_attributes::{EccFamily, Type};
+use picky_asn1_x509::RsaPublicKey;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// Fingerprints of a public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprints {
+    /// SHA-256 of the DER SubjectPublicKeyInfo, in lowercase hexadecimal.
+    pub spki_sha256: String,
+    /// RFC 7638 JWK thumbprint, in base 64 URL without padding, if the key can be written as a JWK.
+    pub jwk_thumbprint: Option<String>,
+    /// SHA-256 of the OpenSSH public key, if the key can be used by OpenSSH.
+    pub ssh_sha256: Option<Vec<u8>>,
+    /// SSHFP algorithm number of the key, if the key can be used by OpenSSH.
+    pub sshfp_algorithm: Option<u8>,
+}
+
+/// One kind of fingerprint, or DNS record containing a fingerprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FingerprintKind {
+    /// SHA-256 of the DER SubjectPublicKeyInfo.
+    SpkiSha256,
+    /// RFC 7638 JWK thumbprint.
+    Jwk,
+    /// OpenSSH `SHA256:` fingerprint.
+    Ssh,
+    /// DANE TLSA 3 1 1 record.
+    Tlsa,
+    /// SSHFP record with a SHA-256 fingerprint.
+    Sshfp,
+}
+
+impl FromStr for FingerprintKind {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "spki-sha256" => Ok(FingerprintKind::SpkiSha256),
+            "jwk" => Ok(FingerprintKind::Jwk),
+            "ssh" => Ok(FingerprintKind::Ssh),
+            "tlsa" => Ok(FingerprintKind::Tlsa),
+            "sshfp" => Ok(FingerprintKind::Sshfp),
+            _ => Err(format!(
+                "unknown fingerprint \"{}\", expected spki-sha256, jwk, ssh, tlsa or sshfp",
+                input
+            )),
+        }
+    }
+}
+
+impl Fingerprints {
+    /// Computes the fingerprints of a public key in the format of `psa_export_public_key`.
+    pub fn new(key_type: Type, bits: usize, public_key: &[u8]) -> Result<Self> {
+        let spki = encode_spki(key_type, bits, public_key)?;
+        let (jwk, ssh) = match key_type {
+            Type::RsaKeyPair | Type::RsaPublicKey => {
+                let key: RsaPublicKey = picky_asn1_der::from_bytes(public_key).map_err(|_| {
+                    error!("Could not deserialise RSA key");
+                    ToolErrorKind::IncorrectData
+                })?;
+                let jwk = format!(
+                    r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+                    base64url(key.public_exponent.as_unsigned_bytes_be()),
+                    base64url(key.modulus.as_unsigned_bytes_be())
+                );
+                let mut ssh = Vec::new();
+                ssh_string(&mut ssh, b"ssh-rsa");
+                ssh_string(&mut ssh, key.public_exponent.as_signed_bytes_be());
+                ssh_string(&mut ssh, key.modulus.as_signed_bytes_be());
+                (Some(jwk), Some((1, ssh)))
+            }
+            Type::EccKeyPair {
+                curve_family: EccFamily::SecpR1,
+            }
+            | Type::EccPublicKey {
+                curve_family: EccFamily::SecpR1,
+            } if matches!(bits, 256 | 384 | 521) => {
+                let coordinate_length = (bits + 7) / 8;
+                if public_key.len() != 1 + 2 * coordinate_length || public_key[0] != 0x04 {
+                    error!("The public key is not an uncompressed elliptic curve point");
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+                let (x, y) = public_key[1..].split_at(coordinate_length);
+                let jwk = format!(
+                    r#"{{"crv":"P-{}","kty":"EC","x":"{}","y":"{}"}}"#,
+                    bits,
+                    base64url(x),
+                    base64url(y)
+                );
+                let curve = format!("nistp{}", bits);
+                let mut ssh = Vec::new();
+                ssh_string(&mut ssh, format!("ecdsa-sha2-{}", curve).as_bytes());
+                ssh_string(&mut ssh, curve.as_bytes());
+                ssh_string(&mut ssh, public_key);
+                (Some(jwk), Some((3, ssh)))
+            }
+            Type::EccKeyPair {
+                curve_family: EccFamily::Montgomery,
+            }
+            | Type::EccPublicKey {
+                curve_family: EccFamily::Montgomery,
+            } => {
+                let curve = if bits == 255 { "X25519" } else { "X448" };
+                let jwk = format!(
+                    r#"{{"crv":"{}","kty":"OKP","x":"{}"}}"#,
+                    curve,
+                    base64url(public_key)
+                );
+                (Some(jwk), None)
+            }
+            _ => (None, None),
+        };
+
+        Ok(Fingerprints {
+            spki_sha256: hex(&Sha256::digest(&spki)),
+            jwk_thumbprint: jwk.map(|jwk| base64url(&Sha256::digest(jwk.as_bytes()))),
+            ssh_sha256: ssh.as_ref().map(|(_, blob)| Sha256::digest(blob).to_vec()),
+            sshfp_algorithm: ssh.map(|(algorithm, _)| algorithm),
+        })
+    }
+
+    /// Returns the OpenSSH fingerprint, `SHA256:` followed by the hash in base 64 without padding.
+    pub fn ssh(&self) -> Option<String> {
+        self.ssh_sha256.as_ref().map(|hash| {
+            format!(
+                "SHA256:{}",
+                base64::encode_config(hash, base64::STANDARD_NO_PAD)
+            )
+        })
+    }
+
+    /// Returns a DANE TLSA record for a TLS service using the key. Without host, the owner name is
+    /// relative to the zone origin.
+    pub fn tlsa_record(&self, host: Option<&str>, port: u16) -> String {
+        let owner = match host {
+            Some(host) => format!("_{}._tcp.{}.", port, host.trim_end_matches('.')),
+            None => format!("_{}._tcp", port),
+        };
+        format!("{} IN TLSA 3 1 1 {}", owner, self.spki_sha256)
+    }
+
+    /// Returns an SSHFP record for an SSH host key. Without host, the owner name is the zone origin.
+    pub fn sshfp_record(&self, host: Option<&str>) -> Option<String> {
+        let owner = match host {
+            Some(host) => format!("{}.", host.trim_end_matches('.')),
+            None => String::from("@"),
+        };
+        Some(format!(
+            "{} IN SSHFP {} 2 {}",
+            owner,
+            self.sshfp_algorithm?,
+            hex(self.ssh_sha256.as_ref()?)
+        ))
+    }
+
+    /// Returns one fingerprint, with records for the default owner names and port 443.
+    pub fn get(&self, kind: FingerprintKind) -> Option<String> {
+        match kind {
+            FingerprintKind::SpkiSha256 => Some(self.spki_sha256.clone()),
+            FingerprintKind::Jwk => self.jwk_thumbprint.clone(),
+            FingerprintKind::Ssh => self.ssh(),
+            FingerprintKind::Tlsa => Some(self.tlsa_record(None, 443)),
+            FingerprintKind::Sshfp => self.sshfp_record(None),
+        }
+    }
+}
+
+/// Returns true for the key types which have a public key that can be fingerprinted.
+pub fn is_asymmetric(key_type: Type) -> bool {
+    matches!(
+        key_type,
+        Type::RsaKeyPair | Type::RsaPublicKey | Type::EccKeyPair { .. } | Type::EccPublicKey { .. }
+    )
+}
+
+/// Returns the SHA-256 hash, in lowercase hexadecimal, of a public key encoded as a DER
+/// SubjectPublicKeyInfo. `public_key` is in the format of `psa_export_public_key`.
+pub fn spki_sha256(key_type: Type, bits: usize, public_key: &[u8]) -> Result<String> {
+    let spki = encode_spki(key_type, bits, public_key)?;
+    Ok(hex(&Sha256::digest(&spki)))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn base64url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+// Appends a length-prefixed string, as encoded in the SSH wire format (RFC 4251).
+fn ssh_string(buffer: &mut Vec<u8>, data: &[u8]) {
+    buffer.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(data);
+}
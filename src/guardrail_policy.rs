@@ -0,0 +1,145 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Guardrail policy, restricting the destructive and sensitive operations of parsec-tool on a machine.
+//!
+//! The policy is read from `/etc/parsec-tool/policy.toml`, or from the file given by the
+//! `PARSEC_TOOL_POLICY` environment variable. Without a policy file, nothing is restricted:
+//!
+//! ```toml
+//! # Glob patterns of the names of keys which can not be deleted, rotated or migrated away.
+//! protected-keys = ["device-id", "prod-*"]
+//! # Users (names or numerical IDs) allowed to run delete-client. Nobody can if the list is empty.
+//! admins = ["root"]
+//! # Commands deleting keys fail unless --yes is given.
+//! require-yes = true
+//! # IDs of the providers which can be targeted.
+//! allowed-providers = [1, 3]
+//! ```
+//!
+//! The policy protects against mistakes, such as a command run in the wrong shell. It is not access
+//! control: that is done by the Parsec service, for all its clients.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_filter::name_pattern;
+use log::error;
+use nix::unistd::{geteuid, User};
+use parsec_client::core::interface::requests::ProviderId;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const POLICY_FILE: &str = "/etc/parsec-tool/policy.toml";
+const POLICY_FILE_VARIABLE: &str = "PARSEC_TOOL_POLICY";
+
+/// Guardrail policy of parsec-tool.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct GuardrailPolicy {
+    #[serde(default)]
+    protected_keys: Vec<String>,
+    admins: Option<Vec<String>>,
+    #[serde(default)]
+    require_yes: bool,
+    allowed_providers: Option<Vec<u8>>,
+}
+
+impl GuardrailPolicy {
+    /// Reads the policy. It allows everything if there is no policy file.
+    pub fn load() -> Result<Self> {
+        let path = std::env::var_os(POLICY_FILE_VARIABLE)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(POLICY_FILE));
+        if !path.exists() {
+            return Ok(GuardrailPolicy::default());
+        }
+        toml::from_str(&std::fs::read_to_string(&path)?).map_err(|e| {
+            error!(
+                "Could not parse the guardrail policy {}: {}",
+                path.display(),
+                e
+            );
+            ToolErrorKind::IncorrectData.into()
+        })
+    }
+
+    /// Fails if the provider can not be targeted.
+    pub fn check_provider(&self, provider: ProviderId) -> Result<()> {
+        match &self.allowed_providers {
+            Some(allowed) if !allowed.contains(&(provider as u8)) => {
+                error!(
+                    "The guardrail policy does not allow targeting {} (ID {})",
+                    provider, provider as u8
+                );
+                Err(ToolErrorKind::PolicyViolation.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if the key is protected, and so can not be deleted or replaced.
+    pub fn check_protected(&self, name: &str) -> Result<()> {
+        for pattern in &self.protected_keys {
+            if name_pattern(pattern, false)?.is_match(name) {
+                error!(
+                    "Key \"{}\" is protected by the guardrail policy (\"{}\")",
+                    name, pattern
+                );
+                return Err(ToolErrorKind::PolicyViolation.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if the key can not be deleted, because it is protected or because the deletion was not
+    /// confirmed with `--yes`.
+    pub fn check_deletion(&self, name: &str, yes: bool) -> Result<()> {
+        self.check_protected(name)?;
+        if self.require_yes && !yes {
+            error!("The guardrail policy requires --yes to delete keys");
+            return Err(ToolErrorKind::PolicyViolation.into());
+        }
+        Ok(())
+    }
+
+    /// Fails if the user running parsec-tool is not allowed to delete clients.
+    pub fn check_delete_client(&self, yes: bool) -> Result<()> {
+        if let Some(admins) = &self.admins {
+            let user = current_user()?;
+            if !admins.iter().any(|admin| user.contains(admin)) {
+                error!(
+                    "The guardrail policy only allows {} to delete clients",
+                    if admins.is_empty() {
+                        String::from("nobody")
+                    } else {
+                        admins.join(", ")
+                    }
+                );
+                return Err(ToolErrorKind::PolicyViolation.into());
+            }
+        }
+        if self.require_yes && !yes {
+            error!("The guardrail policy requires --yes to delete clients");
+            return Err(ToolErrorKind::PolicyViolation.into());
+        }
+        Ok(())
+    }
+}
+
+// Returns the names of the user running parsec-tool: its numerical ID and, if it has one, its user
+// name, as given by the system user database (/etc/passwd, LDAP...).
+fn current_user() -> Result<Vec<String>> {
+    let uid = geteuid();
+    let mut names = vec![uid.to_string()];
+    match User::from_uid(uid) {
+        Ok(Some(user)) => names.push(user.name),
+        Ok(None) => (),
+        Err(e) => {
+            error!(
+                "Could not look up the name of user {}, which the guardrail policy needs: {}",
+                uid, e
+            );
+            return Err(ToolErrorKind::PolicyViolation.into());
+        }
+    }
+    Ok(names)
+}
@@ -0,0 +1,148 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Aliases of keys rotated with `rotate-key`, stored locally in `aliases.toml` under the configuration
+//! directory of parsec-tool.
+//!
+//! Each rotation of a key creates a new version of it, named `<alias>.v<N>`; the first version is the
+//! key which was named `<alias>` before its first rotation. The alias file records the versions of
+//! every alias, per provider:
+//!
+//! ```toml
+//! [[alias]]
+//! name = "signing-key"
+//! provider = 1
+//! version = 3
+//! versions = ["signing-key", "signing-key.v2", "signing-key.v3"]
+//! retention = 2
+//! ```
+//!
+//! The commands using a key resolve a name which is an alias to its current version.
+
+use crate::error::Result;
+use crate::util::{load_config_file, store_config_file};
+use log::info;
+use parsec_client::core::interface::requests::ProviderId;
+use parsec_client::BasicClient;
+use serde::{Deserialize, Serialize};
+
+const ALIAS_FILE_NAME: &str = "aliases.toml";
+
+/// The aliases of rotated keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AliasFile {
+    #[serde(default, rename = "alias")]
+    aliases: Vec<Alias>,
+}
+
+/// An alias and the versions of its key, from the oldest to the current one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Alias {
+    /// Name of the alias.
+    pub name: String,
+    /// ID of the provider of the keys.
+    pub provider: u8,
+    /// Number of the current version.
+    pub version: u32,
+    /// Names of the versions which still exist, from the oldest to the current one.
+    pub versions: Vec<String>,
+    /// Number of versions to keep, including the current one. All versions are kept if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention: Option<usize>,
+}
+
+impl Alias {
+    /// Creates the alias of a key before its first rotation.
+    pub fn new(name: &str, provider: ProviderId) -> Self {
+        Alias {
+            name: name.to_string(),
+            provider: provider as u8,
+            version: 1,
+            versions: vec![name.to_string()],
+            retention: None,
+        }
+    }
+
+    /// Returns the name of the current version.
+    pub fn current(&self) -> &str {
+        self.versions.last().map_or(&self.name, String::as_str)
+    }
+
+    /// Returns the name of the next version.
+    pub fn next_version(&self) -> String {
+        format!("{}.v{}", self.name, self.version + 1)
+    }
+}
+
+impl AliasFile {
+    /// Reads the alias file. It is empty if it does not exist yet, or if there is no configuration
+    /// directory.
+    pub fn load() -> Result<Self> {
+        load_config_file(ALIAS_FILE_NAME)
+    }
+
+    /// Writes the alias file.
+    pub fn save(&self) -> Result<()> {
+        store_config_file(ALIAS_FILE_NAME, self)
+    }
+
+    /// Returns an alias of a provider.
+    pub fn get(&self, name: &str, provider: ProviderId) -> Option<&Alias> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.name == name && alias.provider == provider as u8)
+    }
+
+    /// Adds or replaces an alias.
+    pub fn set(&mut self, alias: Alias) {
+        match self
+            .aliases
+            .iter_mut()
+            .find(|existing| existing.name == alias.name && existing.provider == alias.provider)
+        {
+            Some(existing) => *existing = alias,
+            None => self.aliases.push(alias),
+        }
+    }
+
+    /// Removes a deleted key from the versions of the aliases of its provider. An alias is removed with
+    /// its current version, the older ones becoming plain keys. Returns true if an alias was changed.
+    pub fn remove_key(&mut self, name: &str, provider: u8) -> bool {
+        let mut changed = false;
+        self.aliases.retain_mut(|alias| {
+            if alias.provider != provider || !alias.versions.iter().any(|version| version == name) {
+                return true;
+            }
+            changed = true;
+            if alias.current() == name {
+                return false;
+            }
+            alias.versions.retain(|version| version != name);
+            true
+        });
+        changed
+    }
+}
+
+/// Returns the names of the versions of a key, the current one first, if the name is an alias of the
+/// implicit provider of the client. Otherwise returns the name itself.
+pub fn key_versions(basic_client: &BasicClient, name: &str) -> Result<Vec<String>> {
+    match AliasFile::load()?.get(name, basic_client.implicit_provider()) {
+        Some(alias) => Ok(alias.versions.iter().rev().cloned().collect()),
+        None => Ok(vec![name.to_string()]),
+    }
+}
+
+/// Returns the name of the current version of a key if the name is an alias of the implicit provider of
+/// the client. Otherwise returns the name itself.
+pub fn resolve_key_name(basic_client: &BasicClient, name: &str) -> Result<String> {
+    match AliasFile::load()?.get(name, basic_client.implicit_provider()) {
+        Some(alias) => {
+            info!("Using key \"{}\" for alias \"{}\".", alias.current(), name);
+            Ok(alias.current().to_string())
+        }
+        None => Ok(name.to_string()),
+    }
+}
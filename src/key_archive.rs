@@ -0,0 +1,261 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encrypted archives of exported keys, written by `backup` and read by `restore`.
+//!
+//! An archive is a JSON document holding the list of keys, with their names, providers, attributes
+//! (in the syntax of key templates) and key material, encrypted with AES-256-GCM:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "protection": { "argon2id": { "salt": "...", "memory-cost": 19456, "time-cost": 2, "parallelism": 1 } },
+//!   "nonce": "...",
+//!   "ciphertext": "..."
+//! }
+//! ```
+//!
+//! The AES key is either derived from a password with Argon2id or randomly generated and encrypted with a
+//! Parsec RSA-OAEP wrapping key (`"protection": { "wrapping-key": { "name": "...", "encrypted-key": "..." } }`).
+//! The protection parameters are authenticated as additional data. Binary fields are encoded in base 64.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_spec::KeyTemplate;
+use crate::util::write_file_atomically;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+const VERSION: u32 = 1;
+
+/// Size in bytes of the AES key encrypting an archive.
+pub const CONTENT_KEY_LENGTH: usize = 32;
+
+/// Size in bytes of the AES-GCM nonce of an archive.
+pub const NONCE_LENGTH: usize = 12;
+
+/// Size in bytes of the Argon2id salt of an archive.
+pub const SALT_LENGTH: usize = 16;
+
+/// Encrypted archive of keys.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct KeyArchive {
+    version: u32,
+    protection: Protection,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Protection of the AES key encrypting an archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Protection {
+    /// The key is derived from a password.
+    #[serde(rename_all = "kebab-case")]
+    Argon2id {
+        /// Salt, in base 64.
+        salt: String,
+        /// Memory size in KiB.
+        memory_cost: u32,
+        /// Number of iterations.
+        time_cost: u32,
+        /// Degree of parallelism.
+        parallelism: u32,
+    },
+    /// The key is encrypted with a Parsec key.
+    #[serde(rename_all = "kebab-case")]
+    WrappingKey {
+        /// Name of the Parsec key.
+        name: String,
+        /// Encrypted AES key, in base 64.
+        encrypted_key: String,
+    },
+}
+
+impl Protection {
+    /// Creates the protection of a key derived from a password with the default Argon2id parameters.
+    pub fn new_argon2id(salt: &[u8]) -> Self {
+        Protection::Argon2id {
+            salt: base64::encode(salt),
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+
+    /// Derives the AES key from a password. Fails if the key is protected by a wrapping key.
+    pub fn derive_key(&self, password: &str) -> Result<Vec<u8>> {
+        match self {
+            Protection::Argon2id {
+                salt,
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => {
+                let params = Params::new(
+                    *memory_cost,
+                    *time_cost,
+                    *parallelism,
+                    Some(CONTENT_KEY_LENGTH),
+                )
+                .map_err(|e| {
+                    error!("Invalid Argon2id parameters: {}", e);
+                    ToolErrorKind::IncorrectData
+                })?;
+                let mut key = vec![0; CONTENT_KEY_LENGTH];
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), &base64::decode(salt)?, &mut key)
+                    .map_err(|e| {
+                        error!("Could not derive the archive key from the password: {}", e);
+                        ToolErrorKind::IncorrectData
+                    })?;
+                Ok(key)
+            }
+            Protection::WrappingKey { name, .. } => {
+                error!(
+                    "The archive is protected by the wrapping key \"{}\", not by a password",
+                    name
+                );
+                Err(ToolErrorKind::WrongKeyAlgorithm.into())
+            }
+        }
+    }
+}
+
+/// A key stored in an archive.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ArchivedKey {
+    /// Name of the key.
+    pub name: String,
+    /// ID of the provider the key was backed up from.
+    pub provider_id: u8,
+    /// Attributes of the key.
+    pub attributes: KeyTemplate,
+    /// Key material in the format of `psa_export_key`, in base 64.
+    pub data: String,
+}
+
+// Do not print the key material.
+impl fmt::Debug for ArchivedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchivedKey")
+            .field("name", &self.name)
+            .field("provider_id", &self.provider_id)
+            .field("attributes", &self.attributes)
+            .finish()
+    }
+}
+
+impl KeyArchive {
+    /// Encrypts keys into an archive.
+    pub fn seal(
+        keys: &[ArchivedKey],
+        protection: Protection,
+        content_key: &[u8],
+        nonce: &[u8],
+    ) -> Result<Self> {
+        if nonce.len() != NONCE_LENGTH {
+            error!("The nonce of the archive has the wrong size");
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+        let plaintext = serde_json::to_vec(keys).map_err(|e| {
+            error!("Could not serialise the keys: {}", e);
+            ToolErrorKind::IncorrectData
+        })?;
+        let aad = additional_data(&protection)?;
+        let ciphertext = cipher(content_key)?
+            .encrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                error!("Could not encrypt the archive");
+                ToolErrorKind::IncorrectData
+            })?;
+
+        Ok(KeyArchive {
+            version: VERSION,
+            protection,
+            nonce: base64::encode(nonce),
+            ciphertext: base64::encode(ciphertext),
+        })
+    }
+
+    /// Decrypts the keys of an archive.
+    pub fn open(&self, content_key: &[u8]) -> Result<Vec<ArchivedKey>> {
+        let nonce = base64::decode(&self.nonce)?;
+        if nonce.len() != NONCE_LENGTH {
+            error!("The nonce of the archive has the wrong size");
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+        let aad = additional_data(&self.protection)?;
+        let plaintext = cipher(content_key)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &base64::decode(&self.ciphertext)?,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                error!("Could not decrypt the archive: wrong password or wrapping key, or corrupted archive");
+                ToolErrorKind::IncorrectData
+            })?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| {
+            error!("Could not deserialise the keys of the archive: {}", e);
+            ToolErrorKind::IncorrectData.into()
+        })
+    }
+
+    /// Returns the protection of the archive key.
+    pub fn protection(&self) -> &Protection {
+        &self.protection
+    }
+
+    /// Reads an archive from a file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let archive: KeyArchive =
+            serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| {
+                error!("Could not parse the key archive: {}", e);
+                ToolErrorKind::IncorrectData
+            })?;
+        if archive.version != VERSION {
+            error!("Unsupported key archive version {}", archive.version);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+        Ok(archive)
+    }
+
+    /// Writes an archive to a file, only readable by its owner.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| {
+            error!("Could not serialise the key archive: {}", e);
+            ToolErrorKind::IncorrectData
+        })?;
+        write_file_atomically(path, contents.as_bytes(), 0o600)
+    }
+}
+
+fn cipher(content_key: &[u8]) -> Result<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(content_key).map_err(|_| {
+        error!("The archive key has the wrong size");
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+fn additional_data(protection: &Protection) -> Result<Vec<u8>> {
+    serde_json::to_vec(protection).map_err(|e| {
+        error!("Could not serialise the archive protection: {}", e);
+        ToolErrorKind::IncorrectData.into()
+    })
+}
@@ -0,0 +1,102 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Selection of keys from the output of `list_keys`, for the commands working on several keys.
+//!
+//! Keys can be selected by name, with glob patterns (`*`, `?` and `[...]`) or regular expressions, and
+//! by provider, key type or permitted algorithm.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_spec::{key_type_to_string, parse_algorithm};
+use log::error;
+use parsec_client::core::interface::operations::list_keys::KeyInfo;
+use parsec_client::core::interface::operations::psa_key_attributes::Type;
+use regex::Regex;
+use structopt::StructOpt;
+
+/// Filters on the attributes of keys.
+#[derive(Debug, StructOpt)]
+pub struct KeyFilter {
+    /// Only select the keys of the provider with this ID.
+    #[structopt(long = "provider")]
+    provider: Option<u8>,
+
+    /// Only select the keys of this type, e.g. "rsa-key-pair", "ecc-key-pair(secp-r1)" or
+    /// "ecc-key-pair" for any curve family.
+    #[structopt(long = "type")]
+    key_type: Option<String>,
+
+    /// Only select the keys whose permitted algorithm is this one, e.g. "ecdsa(sha256)".
+    #[structopt(long = "algorithm")]
+    algorithm: Option<String>,
+}
+
+impl KeyFilter {
+    /// Returns the keys matching the filters, in the same order.
+    pub fn select(&self, keys: Vec<KeyInfo>) -> Result<Vec<KeyInfo>> {
+        let algorithm = self.algorithm.as_deref().map(parse_algorithm).transpose()?;
+
+        Ok(keys
+            .into_iter()
+            .filter(|key| {
+                self.provider
+                    .map_or(true, |provider| key.provider_id as u8 == provider)
+                    && self.key_type.as_deref().map_or(true, |key_type| {
+                        key_type_matches(key.attributes.key_type, key_type)
+                    })
+                    && algorithm.map_or(true, |algorithm| {
+                        key.attributes.policy.permitted_algorithms == algorithm
+                    })
+            })
+            .collect())
+    }
+}
+
+/// Checks if a key type is `expected`, written as for `create-key --type`. A type without curve or
+/// group family, e.g. "ecc-key-pair", matches all the families.
+pub fn key_type_matches(key_type: Type, expected: &str) -> bool {
+    let name = key_type_to_string(key_type);
+    name == expected || name.split('(').next() == Some(expected)
+}
+
+/// Compiles a key name pattern, a glob pattern unless `regex` is true. The pattern has to match the
+/// whole name.
+pub fn name_pattern(pattern: &str, regex: bool) -> Result<Regex> {
+    let expression = if regex {
+        format!("^(?:{})$", pattern)
+    } else {
+        glob_to_regex(pattern)
+    };
+    Regex::new(&expression).map_err(|e| {
+        error!("Invalid key name pattern \"{}\": {}", pattern, e);
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut expression = String::from("^");
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => expression.push_str(".*"),
+            '?' if !in_class => expression.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                expression.push('[');
+            }
+            '!' if in_class && expression.ends_with('[') => expression.push('^'),
+            ']' if in_class => {
+                in_class = false;
+                expression.push(']');
+            }
+            '\\' | '^' | '[' if in_class => {
+                expression.push('\\');
+                expression.push(c);
+            }
+            _ if in_class => expression.push(c),
+            _ => expression.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expression.push('$');
+    expression
+}
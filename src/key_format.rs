@@ -0,0 +1,528 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversions between the key formats used by the Parsec service and standard key encodings.
+//!
+//! The Parsec service exchanges key material in the formats described in the [Parsec
+//! Book](https://parallaxsecond.github.io/parsec-book/parsec_client/operations/psa_import_key.html):
+//! PKCS#1 `RSAPrivateKey`/`RSAPublicKey` for RSA keys, the raw private value for ECC key pairs and the
+//! uncompressed point for ECC public keys. The functions below translate between those and the
+//! PKCS#8, SEC1 and SubjectPublicKeyInfo encodings used by most other tools.
+
+use crate::error::{Result, ToolErrorKind};
+use log::error;
+use oid::prelude::*;
+use parsec_client::core::interface::operations::psa_key_attributes::{EccFamily, Type};
+use picky_asn1::bit_string::BitString;
+use picky_asn1::wrapper::IntegerAsn1;
+use picky_asn1_x509::{
+    oids, AlgorithmIdentifier, AlgorithmIdentifierParameters, ECPrivateKey, PrivateKeyInfo,
+    PrivateKeyValue, PublicKey, RsaPrivateKey, RsaPublicKey, SubjectPublicKeyInfo,
+};
+use serde::Deserialize;
+use std::fmt;
+
+// Iteration count used when encrypting PKCS#8 private keys with PBKDF2.
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Key material decoded from a standard encoding, in the format expected by `psa_import_key`.
+pub struct DecodedKey {
+    /// Type of the key.
+    pub key_type: Type,
+    /// Size of the key in bits.
+    pub bits: usize,
+    /// Key material in the PSA format of `key_type`.
+    pub data: Vec<u8>,
+}
+
+impl fmt::Debug for DecodedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Do not leak private key material in logs.
+        f.debug_struct("DecodedKey")
+            .field("key_type", &self.key_type)
+            .field("bits", &self.bits)
+            .finish()
+    }
+}
+
+/// Decodes a PEM or DER encoded key.
+///
+/// Supported encodings are PKCS#1 RSA private and public keys, PKCS#8 private keys (optionally encrypted
+/// with a password), SEC1 EC private keys and SubjectPublicKeyInfo public keys. The encoding of DER
+/// input is detected by trying each of the encodings in turn.
+pub fn decode_key(input: &[u8], password: Option<&str>) -> Result<DecodedKey> {
+    if let Ok(pem) = pem::parse(input) {
+        return match pem.tag.as_str() {
+            "PRIVATE KEY" => decode_pkcs8(&pem.contents),
+            "ENCRYPTED PRIVATE KEY" => decode_encrypted_pkcs8(&pem.contents, password),
+            "RSA PRIVATE KEY" => decode_pkcs1_private(&pem.contents),
+            "EC PRIVATE KEY" => decode_sec1(&pem.contents, None),
+            "PUBLIC KEY" => decode_spki(&pem.contents),
+            "RSA PUBLIC KEY" => decode_pkcs1_public(&pem.contents),
+            other => {
+                error!("Unsupported PEM label \"{}\"", other);
+                Err(ToolErrorKind::NotSupported.into())
+            }
+        };
+    }
+
+    // The encoding of DER input is detected by parsing it silently, so that the errors logged are
+    // the ones of the encoding it is in.
+    if is_der::<PrivateKeyInfo>(input) {
+        decode_pkcs8(input)
+    } else if is_der::<RsaPrivateKey>(input) {
+        decode_pkcs1_private(input)
+    } else if is_der::<ECPrivateKey>(input) {
+        decode_sec1(input, None)
+    } else if is_der::<SubjectPublicKeyInfo>(input) {
+        decode_spki(input)
+    } else if is_der::<RsaPublicKey>(input) {
+        decode_pkcs1_public(input)
+    } else if pkcs8::EncryptedPrivateKeyInfo::try_from(input).is_ok() {
+        decode_encrypted_pkcs8(input, password)
+    } else {
+        error!("Input is not a supported PEM or DER encoded key");
+        Err(ToolErrorKind::IncorrectData.into())
+    }
+}
+
+fn is_der<'a, T: Deserialize<'a>>(der: &'a [u8]) -> bool {
+    picky_asn1_der::from_bytes::<T>(der).is_ok()
+}
+
+/// Encodes a public key exported from the Parsec service as a `SubjectPublicKeyInfo`.
+///
+/// Montgomery curve keys are encoded as described in RFC 8410, with the X25519 or X448 algorithm
+/// identifiers.
+pub fn encode_spki(key_type: Type, bits: usize, public_key: &[u8]) -> Result<Vec<u8>> {
+    let subject_public_key_info = match key_type {
+        Type::RsaKeyPair | Type::RsaPublicKey => SubjectPublicKeyInfo {
+            algorithm: AlgorithmIdentifier::new_rsa_encryption(),
+            subject_public_key: PublicKey::Rsa(
+                picky_asn1_der::from_bytes::<RsaPublicKey>(public_key)
+                    .map_err(|_| {
+                        error!("Could not deserialise RSA key");
+                        ToolErrorKind::IncorrectData
+                    })?
+                    .into(),
+            ),
+        },
+        Type::EccKeyPair {
+            curve_family: EccFamily::Montgomery,
+        }
+        | Type::EccPublicKey {
+            curve_family: EccFamily::Montgomery,
+        } => {
+            let algorithm = match bits {
+                255 => AlgorithmIdentifier::new_x25519(),
+                448 => AlgorithmIdentifier::new_x448(),
+                _ => {
+                    error!("Unsupported number of bits {} for Montgomery curve", bits);
+                    return Err(ToolErrorKind::NotSupported.into());
+                }
+            };
+            SubjectPublicKeyInfo {
+                algorithm,
+                subject_public_key: PublicKey::Ed(
+                    BitString::with_bytes(public_key.to_vec()).into(),
+                ),
+            }
+        }
+        Type::EccKeyPair { curve_family } | Type::EccPublicKey { curve_family } => {
+            SubjectPublicKeyInfo::new_ec_key(
+                curve_oid(curve_family, bits)?,
+                BitString::with_bytes(public_key.to_vec()),
+            )
+        }
+        _ => {
+            error!("Unsupported type of key");
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+
+    picky_asn1_der::to_vec(&subject_public_key_info).map_err(|_| {
+        error!("Could not serialise public key");
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+/// Encodes a private key exported from the Parsec service as a PKCS#8 `PrivateKeyInfo`.
+///
+/// For ECC key pairs, `public_key` is the exported public point and is embedded in the encoding if given.
+pub fn encode_pkcs8(
+    key_type: Type,
+    bits: usize,
+    private_key: &[u8],
+    public_key: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let private_key_info = match key_type {
+        Type::RsaKeyPair => PrivateKeyInfo {
+            version: 0,
+            private_key_algorithm: AlgorithmIdentifier::new_rsa_encryption(),
+            private_key: PrivateKeyValue::RSA(
+                picky_asn1_der::from_bytes::<RsaPrivateKey>(private_key)
+                    .map_err(|_| {
+                        error!("Could not deserialise RSA private key");
+                        ToolErrorKind::IncorrectData
+                    })?
+                    .into(),
+            ),
+            public_key: None,
+        },
+        Type::EccKeyPair { curve_family } => PrivateKeyInfo::new_ec_encryption(
+            curve_oid(curve_family, bits)?,
+            private_key.to_vec(),
+            public_key.map(BitString::with_bytes),
+            false,
+        ),
+        _ => {
+            error!("Unsupported type of key");
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+
+    picky_asn1_der::to_vec(&private_key_info).map_err(|_| {
+        error!("Could not serialise private key");
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+/// Encodes an ECC private key exported from the Parsec service as a SEC1 `ECPrivateKey`.
+pub fn encode_sec1(
+    key_type: Type,
+    bits: usize,
+    private_key: &[u8],
+    public_key: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    if !key_type.is_ecc_key_pair() {
+        error!("SEC1 format only supports ECC keys");
+        return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+    }
+
+    let pkcs8 = encode_pkcs8(key_type, bits, private_key, public_key)?;
+    let private_key_info: PrivateKeyInfo = picky_asn1_der::from_bytes(&pkcs8).map_err(|_| {
+        error!("Could not deserialise PKCS#8 private key");
+        ToolErrorKind::IncorrectData
+    })?;
+    match private_key_info.private_key {
+        PrivateKeyValue::EC(ec_private_key) => {
+            picky_asn1_der::to_vec(&ec_private_key.0).map_err(|_| {
+                error!("Could not serialise ECC private key");
+                ToolErrorKind::IncorrectData.into()
+            })
+        }
+        _ => Err(ToolErrorKind::IncorrectData.into()),
+    }
+}
+
+/// Encrypts a PKCS#8 `PrivateKeyInfo` with a password, producing an `EncryptedPrivateKeyInfo`.
+///
+/// PBES2 is used with PBKDF2-SHA256 and AES-256-CBC. The salt and IV must be random and are
+/// expected to come from the Parsec service.
+pub fn encrypt_pkcs8(
+    private_key_info: &[u8],
+    password: &str,
+    salt: &[u8],
+    iv: &[u8; 16],
+) -> Result<Vec<u8>> {
+    let parameters =
+        pkcs8::pkcs5::pbes2::Parameters::pbkdf2_sha256_aes256cbc(PBKDF2_ITERATIONS, salt, iv)
+            .map_err(|_| {
+                error!("Invalid PBES2 parameters");
+                ToolErrorKind::IncorrectData
+            })?;
+    let encrypted = pkcs8::PrivateKeyInfo::try_from(private_key_info)
+        .and_then(|private_key_info| private_key_info.encrypt_with_params(parameters, password))
+        .map_err(|_| {
+            error!("Could not encrypt the PKCS#8 private key");
+            ToolErrorKind::IncorrectData
+        })?;
+    Ok(encrypted.as_bytes().to_vec())
+}
+
+fn decode_pkcs8(der: &[u8]) -> Result<DecodedKey> {
+    let private_key_info: PrivateKeyInfo = picky_asn1_der::from_bytes(der).map_err(|_| {
+        error!("Could not deserialise PKCS#8 private key");
+        ToolErrorKind::IncorrectData
+    })?;
+
+    match private_key_info.private_key {
+        PrivateKeyValue::RSA(rsa_private_key) => rsa_key_pair(&rsa_private_key.0),
+        PrivateKeyValue::EC(ec_private_key) => {
+            let curve = match private_key_info.private_key_algorithm.parameters() {
+                AlgorithmIdentifierParameters::Ec(parameters) => parameters.curve_oid().clone(),
+                _ => {
+                    error!("PKCS#8 EC private key does not specify a named curve");
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+            };
+            ecc_key_pair(&ec_private_key.0, Some(curve))
+        }
+        PrivateKeyValue::ED(_) => {
+            error!("Edwards and Montgomery curve keys are not supported");
+            Err(ToolErrorKind::NotSupported.into())
+        }
+    }
+}
+
+fn decode_encrypted_pkcs8(der: &[u8], password: Option<&str>) -> Result<DecodedKey> {
+    let encrypted_private_key_info =
+        pkcs8::EncryptedPrivateKeyInfo::try_from(der).map_err(|_| {
+            error!("Could not deserialise encrypted PKCS#8 private key");
+            ToolErrorKind::IncorrectData
+        })?;
+
+    let password = password.ok_or_else(|| {
+        error!("The private key is encrypted but no password was given");
+        ToolErrorKind::NoInput
+    })?;
+
+    let private_key_info = encrypted_private_key_info.decrypt(password).map_err(|_| {
+        error!("Could not decrypt the PKCS#8 private key (wrong password?)");
+        ToolErrorKind::IncorrectData
+    })?;
+
+    decode_pkcs8(private_key_info.as_bytes())
+}
+
+fn decode_pkcs1_private(der: &[u8]) -> Result<DecodedKey> {
+    let rsa_private_key: RsaPrivateKey = picky_asn1_der::from_bytes(der).map_err(|_| {
+        error!("Could not deserialise PKCS#1 RSA private key");
+        ToolErrorKind::IncorrectData
+    })?;
+    rsa_key_pair(&rsa_private_key)
+}
+
+fn decode_pkcs1_public(der: &[u8]) -> Result<DecodedKey> {
+    let rsa_public_key: RsaPublicKey = picky_asn1_der::from_bytes(der).map_err(|_| {
+        error!("Could not deserialise PKCS#1 RSA public key");
+        ToolErrorKind::IncorrectData
+    })?;
+    rsa_public(&rsa_public_key)
+}
+
+fn decode_sec1(der: &[u8], curve: Option<ObjectIdentifier>) -> Result<DecodedKey> {
+    let ec_private_key: ECPrivateKey = picky_asn1_der::from_bytes(der).map_err(|_| {
+        error!("Could not deserialise SEC1 EC private key");
+        ToolErrorKind::IncorrectData
+    })?;
+    ecc_key_pair(&ec_private_key, curve)
+}
+
+fn decode_spki(der: &[u8]) -> Result<DecodedKey> {
+    let subject_public_key_info: SubjectPublicKeyInfo =
+        picky_asn1_der::from_bytes(der).map_err(|_| {
+            error!("Could not deserialise SubjectPublicKeyInfo public key");
+            ToolErrorKind::IncorrectData
+        })?;
+
+    match subject_public_key_info.subject_public_key {
+        PublicKey::Rsa(rsa_public_key) => rsa_public(&rsa_public_key.0),
+        PublicKey::Ec(point) => {
+            let curve = match subject_public_key_info.algorithm.parameters() {
+                AlgorithmIdentifierParameters::Ec(parameters) => parameters.curve_oid(),
+                _ => {
+                    error!("EC public key does not specify a named curve");
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+            };
+            let (curve_family, bits) = curve_from_oid(curve)?;
+            Ok(DecodedKey {
+                key_type: Type::EccPublicKey { curve_family },
+                bits,
+                data: point.0.payload_view().to_vec(),
+            })
+        }
+        PublicKey::Ed(point) => {
+            let oid: String = subject_public_key_info.algorithm.oid().into();
+            let bits = match oid.as_str() {
+                oids::X25519 => 255,
+                oids::X448 => 448,
+                _ => {
+                    error!("Edwards curve keys are not supported");
+                    return Err(ToolErrorKind::NotSupported.into());
+                }
+            };
+            Ok(DecodedKey {
+                key_type: Type::EccPublicKey {
+                    curve_family: EccFamily::Montgomery,
+                },
+                bits,
+                data: point.0.payload_view().to_vec(),
+            })
+        }
+    }
+}
+
+fn rsa_key_pair(rsa_private_key: &RsaPrivateKey) -> Result<DecodedKey> {
+    Ok(DecodedKey {
+        key_type: Type::RsaKeyPair,
+        bits: integer_bits(&rsa_private_key.modulus),
+        data: picky_asn1_der::to_vec(rsa_private_key).map_err(|_| {
+            error!("Could not serialise RSA private key");
+            ToolErrorKind::IncorrectData
+        })?,
+    })
+}
+
+fn rsa_public(rsa_public_key: &RsaPublicKey) -> Result<DecodedKey> {
+    Ok(DecodedKey {
+        key_type: Type::RsaPublicKey,
+        bits: integer_bits(&rsa_public_key.modulus),
+        data: picky_asn1_der::to_vec(rsa_public_key).map_err(|_| {
+            error!("Could not serialise RSA public key");
+            ToolErrorKind::IncorrectData
+        })?,
+    })
+}
+
+// The curve is taken from the SEC1 structure if present, and from the enclosing PKCS#8 structure
+// otherwise.
+fn ecc_key_pair(
+    ec_private_key: &ECPrivateKey,
+    curve: Option<ObjectIdentifier>,
+) -> Result<DecodedKey> {
+    let curve = match (&(ec_private_key.parameters.0).0, curve) {
+        (Some(parameters), _) => parameters.curve_oid().clone(),
+        (None, Some(curve)) => curve,
+        (None, None) => {
+            error!("EC private key does not specify a named curve");
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    let (curve_family, bits) = curve_from_oid(&curve)?;
+
+    // The PSA format is the private value, big-endian, padded to the size of the curve.
+    let private_value = &ec_private_key.private_key.0;
+    let len = (bits + 7) / 8;
+    if private_value.len() > len {
+        error!("EC private key is too large for the curve");
+        return Err(ToolErrorKind::IncorrectData.into());
+    }
+    let mut data = vec![0; len - private_value.len()];
+    data.extend_from_slice(private_value);
+
+    Ok(DecodedKey {
+        key_type: Type::EccKeyPair { curve_family },
+        bits,
+        data,
+    })
+}
+
+fn integer_bits(integer: &IntegerAsn1) -> usize {
+    let bytes = integer.as_unsigned_bytes_be();
+    match bytes.first() {
+        Some(first) => bytes.len() * 8 - first.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// Returns the object identifier of a named curve.
+pub fn curve_oid(curve: EccFamily, key_bits: usize) -> Result<ObjectIdentifier> {
+    let curve_oid = match curve {
+        // SEC random curves over prime fields.
+        EccFamily::SecpR1 => match key_bits {
+            192 => oids::secp192r1(),
+            224 => oids::secp224r1(),
+            256 => oids::secp256r1(),
+            384 => oids::secp384r1(),
+            521 => oids::secp521r1(),
+            _ => return print_error(curve, key_bits),
+        },
+        // SEC Koblitz curves over prime fields.
+        // OIDs are not defined in picky_asn1_x509::oids and in RFC5480.
+        // Use values from https://www.secg.org/sec2-v2.pdf#subsection.A.2
+        EccFamily::SecpK1 => match key_bits {
+            192 => ObjectIdentifier::try_from(SECP192K1).unwrap(),
+            224 => ObjectIdentifier::try_from(SECP224K1).unwrap(),
+            256 => ObjectIdentifier::try_from(SECP256K1).unwrap(),
+            _ => return print_error(curve, key_bits),
+        },
+        // SEC Koblitz curves over binary fields
+        EccFamily::SectK1 => match key_bits {
+            233 => oids::sect233k1(),
+            283 => oids::sect283k1(),
+            409 => oids::sect409k1(),
+            571 => oids::sect571k1(),
+            _ => return print_error(curve, key_bits),
+        },
+        // SEC random curves over binary fields
+        EccFamily::SectR1 => match key_bits {
+            233 => oids::sect233r1(),
+            283 => oids::sect283r1(),
+            409 => oids::sect409r1(),
+            571 => oids::sect571r1(),
+            _ => return print_error(curve, key_bits),
+        },
+        // Brainpool curves over prime fields, from RFC 5639.
+        EccFamily::BrainpoolPR1 => match key_bits {
+            160 => ObjectIdentifier::try_from(BRAINPOOLP160R1).unwrap(),
+            192 => ObjectIdentifier::try_from(BRAINPOOLP192R1).unwrap(),
+            224 => ObjectIdentifier::try_from(BRAINPOOLP224R1).unwrap(),
+            256 => ObjectIdentifier::try_from(BRAINPOOLP256R1).unwrap(),
+            320 => ObjectIdentifier::try_from(BRAINPOOLP320R1).unwrap(),
+            384 => ObjectIdentifier::try_from(BRAINPOOLP384R1).unwrap(),
+            512 => ObjectIdentifier::try_from(BRAINPOOLP512R1).unwrap(),
+            _ => return print_error(curve, key_bits),
+        },
+        _ => {
+            error!("Unsupported Ecc family \"{}\"", curve);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+    Ok(curve_oid)
+}
+
+/// Returns the ECC family and key size of a named curve. This is the inverse of `curve_oid`.
+pub fn curve_from_oid(oid: &ObjectIdentifier) -> Result<(EccFamily, usize)> {
+    let oid: String = oid.into();
+    let curve = match oid.as_str() {
+        oids::SECP192R1 => (EccFamily::SecpR1, 192),
+        oids::SECP224R1 => (EccFamily::SecpR1, 224),
+        oids::SECP256R1 => (EccFamily::SecpR1, 256),
+        oids::SECP384R1 => (EccFamily::SecpR1, 384),
+        oids::SECP521R1 => (EccFamily::SecpR1, 521),
+        SECP192K1 => (EccFamily::SecpK1, 192),
+        SECP224K1 => (EccFamily::SecpK1, 224),
+        SECP256K1 => (EccFamily::SecpK1, 256),
+        oids::SECT233K1 => (EccFamily::SectK1, 233),
+        oids::SECT283K1 => (EccFamily::SectK1, 283),
+        oids::SECT409K1 => (EccFamily::SectK1, 409),
+        oids::SECT571K1 => (EccFamily::SectK1, 571),
+        oids::SECT233R1 => (EccFamily::SectR1, 233),
+        oids::SECT283R1 => (EccFamily::SectR1, 283),
+        oids::SECT409R1 => (EccFamily::SectR1, 409),
+        oids::SECT571R1 => (EccFamily::SectR1, 571),
+        BRAINPOOLP160R1 => (EccFamily::BrainpoolPR1, 160),
+        BRAINPOOLP192R1 => (EccFamily::BrainpoolPR1, 192),
+        BRAINPOOLP224R1 => (EccFamily::BrainpoolPR1, 224),
+        BRAINPOOLP256R1 => (EccFamily::BrainpoolPR1, 256),
+        BRAINPOOLP320R1 => (EccFamily::BrainpoolPR1, 320),
+        BRAINPOOLP384R1 => (EccFamily::BrainpoolPR1, 384),
+        BRAINPOOLP512R1 => (EccFamily::BrainpoolPR1, 512),
+        _ => {
+            error!("Unsupported named curve \"{}\"", oid);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+    Ok(curve)
+}
+
+const SECP192K1: &str = "1.3.132.0.31";
+const SECP224K1: &str = "1.3.132.0.32";
+const SECP256K1: &str = "1.3.132.0.10";
+const BRAINPOOLP160R1: &str = "1.3.36.3.3.2.8.1.1.1";
+const BRAINPOOLP192R1: &str = "1.3.36.3.3.2.8.1.1.3";
+const BRAINPOOLP224R1: &str = "1.3.36.3.3.2.8.1.1.5";
+const BRAINPOOLP256R1: &str = "1.3.36.3.3.2.8.1.1.7";
+const BRAINPOOLP320R1: &str = "1.3.36.3.3.2.8.1.1.9";
+const BRAINPOOLP384R1: &str = "1.3.36.3.3.2.8.1.1.11";
+const BRAINPOOLP512R1: &str = "1.3.36.3.3.2.8.1.1.13";
+
+fn print_error(curve: EccFamily, key_bits: usize) -> Result<ObjectIdentifier> {
+    error!(
+        "Unsupported number of bits {} for Ecc family \"{}\"",
+        key_bits, curve
+    );
+    Err(ToolErrorKind::NotSupported.into())
+}
@@ -0,0 +1,231 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Manifests of the keys which should exist in the service, read by `keys plan` and `keys apply`.
+//!
+//! A manifest lists keys with their name, optionally the ID of their provider (the provider of the
+//! command otherwise) and their attributes, using the syntax of key templates. It is written in TOML,
+//! or in JSON or YAML if the file name ends with `.json`, `.yaml` or `.yml`:
+//!
+//! ```toml
+//! [[keys]]
+//! name = "gateway-identity"
+//! provider = 3
+//! type = "ecc-key-pair(secp-r1)"
+//! bits = 256
+//! usage = ["sign", "verify"]
+//! algorithm = "ecdsa(sha256)"
+//! ```
+//!
+//! Keys of the providers used by the manifest which are not listed in it are considered extra keys.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_spec::KeyTemplate;
+use log::error;
+use parsec_client::core::interface::operations::list_keys::KeyInfo;
+use parsec_client::core::interface::operations::psa_key_attributes::Attributes;
+use parsec_client::core::interface::requests::ProviderId;
+use serde::Deserialize;
+use std::convert::TryInto;
+use std::path::Path;
+
+/// Keys which should exist in the service.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Manifest {
+    /// The keys, in the order in which they are created.
+    #[serde(default)]
+    pub keys: Vec<ManifestKey>,
+}
+
+/// A key of a manifest.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestKey {
+    /// Name of the key.
+    pub name: String,
+    /// ID of the provider of the key.
+    pub provider: Option<u8>,
+    /// Type of the key, as in key templates.
+    #[serde(rename = "type")]
+    pub key_type: Option<String>,
+    /// Size of the key in bits, as in key templates.
+    pub bits: Option<usize>,
+    /// Lifetime of the key, as in key templates.
+    pub lifetime: Option<String>,
+    /// Usage flags of the key, as in key templates.
+    #[serde(default)]
+    pub usage: Vec<String>,
+    /// Permitted algorithm of the key, as in key templates.
+    pub algorithm: Option<String>,
+}
+
+impl ManifestKey {
+    /// Returns the template of the attributes of the key.
+    pub fn template(&self) -> KeyTemplate {
+        KeyTemplate {
+            key_type: self.key_type.clone(),
+            bits: self.bits,
+            lifetime: self.lifetime.clone(),
+            usage: self.usage.clone(),
+            algorithm: self.algorithm.clone(),
+        }
+    }
+}
+
+/// Change needed for a key to match a manifest.
+#[derive(Debug)]
+pub struct KeyChange {
+    /// Name of the key.
+    pub name: String,
+    /// Provider of the key.
+    pub provider: ProviderId,
+    /// What differs between the key and the manifest.
+    pub kind: ChangeKind,
+}
+
+/// Difference between a key and a manifest.
+#[derive(Debug)]
+pub enum ChangeKind {
+    /// The key is in the manifest and exists with the same attributes.
+    Unchanged,
+    /// The key is in the manifest but does not exist; it has to be created with these attributes.
+    Create(Attributes),
+    /// The key exists with attributes different from the manifest, described as
+    /// `field: actual value, expected value` strings.
+    Drift(Vec<String>),
+    /// The key exists but is not in the manifest.
+    Extra,
+}
+
+impl Manifest {
+    /// Reads a manifest from a file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let manifest = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|e| {
+                error!("Could not parse the JSON key manifest: {}", e);
+                ToolErrorKind::IncorrectData
+            })?,
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&contents).map_err(|e| {
+                error!("Could not parse the YAML key manifest: {}", e);
+                ToolErrorKind::IncorrectData
+            })?,
+            _ => toml::from_str(&contents).map_err(|e| {
+                error!("Could not parse the TOML key manifest: {}", e);
+                ToolErrorKind::IncorrectData
+            })?,
+        };
+        Ok(manifest)
+    }
+
+    /// Compares the manifest with the existing keys. Keys without provider in the manifest belong to
+    /// `default_provider`.
+    ///
+    /// The changes of the keys of the manifest come first, in the order of the manifest, followed by
+    /// the extra keys.
+    pub fn plan(&self, default_provider: ProviderId, keys: &[KeyInfo]) -> Result<Vec<KeyChange>> {
+        let mut changes: Vec<KeyChange> = Vec::new();
+        for key in &self.keys {
+            let provider = match key.provider {
+                Some(id) => id.try_into().map_err(|_| {
+                    error!(
+                        "The provider ID {} of key \"{}\" does not map with an existing provider",
+                        id, key.name
+                    );
+                    ToolErrorKind::IncorrectData
+                })?,
+                None => default_provider,
+            };
+            if changes
+                .iter()
+                .any(|change| change.name == key.name && change.provider == provider)
+            {
+                error!("Key \"{}\" is listed twice in the manifest", key.name);
+                return Err(ToolErrorKind::IncorrectData.into());
+            }
+            let attributes = key.template().to_attributes().map_err(|e| {
+                error!("Invalid attributes for key \"{}\"", key.name);
+                e
+            })?;
+
+            let kind = match keys
+                .iter()
+                .find(|existing| existing.name == key.name && existing.provider_id == provider)
+            {
+                None => ChangeKind::Create(attributes),
+                Some(existing) => {
+                    let differences = differences(&existing.attributes, &attributes);
+                    if differences.is_empty() {
+                        ChangeKind::Unchanged
+                    } else {
+                        ChangeKind::Drift(differences)
+                    }
+                }
+            };
+            changes.push(KeyChange {
+                name: key.name.clone(),
+                provider,
+                kind,
+            });
+        }
+
+        let providers: Vec<ProviderId> = changes.iter().map(|change| change.provider).collect();
+        let extra: Vec<KeyChange> = keys
+            .iter()
+            .filter(|existing| {
+                providers.contains(&existing.provider_id)
+                    && !changes.iter().any(|change| {
+                        change.name == existing.name && change.provider == existing.provider_id
+                    })
+            })
+            .map(|existing| KeyChange {
+                name: existing.name.clone(),
+                provider: existing.provider_id,
+                kind: ChangeKind::Extra,
+            })
+            .collect();
+        changes.extend(extra);
+
+        Ok(changes)
+    }
+}
+
+fn differences(actual: &Attributes, expected: &Attributes) -> Vec<String> {
+    let actual = KeyTemplate::from_attributes(actual);
+    let expected = KeyTemplate::from_attributes(expected);
+    let mut differences = Vec::new();
+
+    let mut compare = |field: &str, actual: String, expected: String| {
+        if actual != expected {
+            differences.push(format!("{}: {}, expected {}", field, actual, expected));
+        }
+    };
+    compare(
+        "type",
+        actual.key_type.unwrap_or_default(),
+        expected.key_type.unwrap_or_default(),
+    );
+    compare(
+        "bits",
+        actual.bits.unwrap_or_default().to_string(),
+        expected.bits.unwrap_or_default().to_string(),
+    );
+    compare(
+        "lifetime",
+        actual.lifetime.unwrap_or_default(),
+        expected.lifetime.unwrap_or_default(),
+    );
+    compare(
+        "usage",
+        format!("[{}]", actual.usage.join(", ")),
+        format!("[{}]", expected.usage.join(", ")),
+    );
+    compare(
+        "algorithm",
+        actual.algorithm.unwrap_or_default(),
+        expected.algorithm.unwrap_or_default(),
+    );
+
+    differences
+}
@@ -0,0 +1,163 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local metadata of keys, stored in `metadata.toml` under the configuration directory of parsec-tool.
+//!
+//! Parsec keys only have a name and attributes. The metadata store records, for keys identified by
+//! their provider and name, an owner, a description, a not-after date and arbitrary labels:
+//!
+//! ```toml
+//! [[key]]
+//! name = "gateway-identity"
+//! provider = 3
+//! owner = "platform-team@example.com"
+//! description = "TLS client identity of the gateway"
+//! not-after = "2025-06-30"
+//!
+//! [key.labels]
+//! environment = "production"
+//! ```
+//!
+//! The metadata is only stored locally: it is not known to the Parsec service, nor to other machines.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_alias::AliasFile;
+use crate::util::{load_config_file, store_config_file};
+use log::error;
+use parsec_client::core::interface::requests::ProviderId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const METADATA_FILE_NAME: &str = "metadata.toml";
+
+/// The metadata of all keys.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MetadataStore {
+    #[serde(default, rename = "key")]
+    keys: Vec<KeyMetadata>,
+}
+
+/// The metadata of a key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct KeyMetadata {
+    /// Name of the key.
+    pub name: String,
+    /// ID of the provider of the key.
+    pub provider: u8,
+    /// Person or team responsible for the key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Why the key exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Date after which the key should not be used, as YYYY-MM-DD.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<String>,
+    /// Arbitrary labels.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl KeyMetadata {
+    /// Creates empty metadata for a key.
+    pub fn new(name: &str, provider: ProviderId) -> Self {
+        KeyMetadata {
+            name: name.to_string(),
+            provider: provider as u8,
+            owner: None,
+            description: None,
+            not_after: None,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true if no metadata is set.
+    pub fn is_empty(&self) -> bool {
+        self.owner.is_none()
+            && self.description.is_none()
+            && self.not_after.is_none()
+            && self.labels.is_empty()
+    }
+
+    /// Returns true if the key has a label, given as `name` or `name=value`.
+    pub fn has_label(&self, label: &str) -> bool {
+        match label.split_once('=') {
+            Some((name, value)) => self.labels.get(name).map_or(false, |v| v == value),
+            None => self.labels.contains_key(label),
+        }
+    }
+}
+
+impl MetadataStore {
+    /// Reads the metadata store. It is empty if it does not exist yet, or if there is no configuration
+    /// directory.
+    pub fn load() -> Result<Self> {
+        load_config_file(METADATA_FILE_NAME)
+    }
+
+    /// Writes the metadata store.
+    pub fn save(&self) -> Result<()> {
+        store_config_file(METADATA_FILE_NAME, self)
+    }
+
+    /// Returns the metadata of a key.
+    pub fn get(&self, name: &str, provider: ProviderId) -> Option<&KeyMetadata> {
+        self.keys
+            .iter()
+            .find(|key| key.name == name && key.provider == provider as u8)
+    }
+
+    /// Adds or replaces the metadata of a key. Empty metadata is removed.
+    pub fn set(&mut self, metadata: KeyMetadata) {
+        let _ = self.remove(&metadata.name, metadata.provider);
+        if !metadata.is_empty() {
+            self.keys.push(metadata);
+        }
+    }
+
+    /// Removes the metadata of a key. Returns true if the key had metadata.
+    pub fn remove(&mut self, name: &str, provider: u8) -> bool {
+        let count = self.keys.len();
+        self.keys
+            .retain(|key| !(key.name == name && key.provider == provider));
+        self.keys.len() != count
+    }
+}
+
+/// Removes the metadata of a deleted key, and the key from the versions of its aliases, if any.
+pub fn remove_key_metadata(name: &str, provider: ProviderId) -> Result<()> {
+    let mut store = MetadataStore::load()?;
+    if store.remove(name, provider as u8) {
+        store.save()?;
+    }
+    let mut aliases = AliasFile::load()?;
+    if aliases.remove_key(name, provider as u8) {
+        aliases.save()?;
+    }
+    Ok(())
+}
+
+/// Checks that a date is written as YYYY-MM-DD. Dates in this format can be compared as strings.
+pub fn parse_date(input: &str) -> Result<String> {
+    let parts: Vec<&str> = input.split('-').collect();
+    let valid = match parts.as_slice() {
+        [year, month, day] => {
+            year.len() == 4
+                && month.len() == 2
+                && day.len() == 2
+                && parts
+                    .iter()
+                    .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+                && matches!(month.parse::<u8>(), Ok(1..=12))
+                && matches!(day.parse::<u8>(), Ok(1..=31))
+        }
+        _ => false,
+    };
+    if !valid {
+        error!("Invalid date \"{}\", expected YYYY-MM-DD", input);
+        return Err(ToolErrorKind::IncorrectData.into());
+    }
+    Ok(input.to_string())
+}
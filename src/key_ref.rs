@@ -0,0 +1,134 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! References to keys, given to the `--key-name` options.
+//!
+//! A reference is either a plain key name, or a URI starting with `parsec:` in the style of PKCS#11 URIs
+//! (RFC 7512), made of `;`-separated attributes:
+//!
+//! ```text
+//! parsec:provider=tpm;key=device-id
+//! parsec:provider-id=3;key=foo
+//! ```
+//!
+//! `key` is the name of the key, in which `%` followed by two hexadecimal digits stands for the byte
+//! with that value, e.g. `%3B` for `;`. `provider` is one of `mbed-crypto`, `pkcs11`, `tpm`,
+//! `trusted-service` or `cryptoauthlib`, and `provider-id` is the numerical ID of a provider. The
+//! provider of a reference overrides the global `--provider` option for the command using the key.
+
+use parsec_client::core::interface::requests::ProviderId;
+use std::convert::TryInto;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+const URI_SCHEME: &str = "parsec:";
+
+/// Reference to a key: its name and, optionally, its provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRef {
+    /// Name of the key.
+    pub name: String,
+    /// Provider of the key, if given in the reference.
+    pub provider: Option<ProviderId>,
+}
+
+impl FromStr for KeyRef {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let attributes = match input.strip_prefix(URI_SCHEME) {
+            Some(attributes) => attributes,
+            None => {
+                return Ok(KeyRef {
+                    name: input.to_string(),
+                    provider: None,
+                })
+            }
+        };
+
+        let mut name = None;
+        let mut provider = None;
+        for attribute in attributes.split(';') {
+            let (attribute, value) = attribute
+                .split_once('=')
+                .ok_or_else(|| format!("invalid key URI attribute \"{}\"", attribute))?;
+            match attribute {
+                "key" => {
+                    if name.replace(percent_decode(value)?).is_some() {
+                        return Err(format!("the key is given twice in \"{}\"", input));
+                    }
+                }
+                "provider" | "provider-id" => {
+                    let id = if attribute == "provider" {
+                        parse_provider_name(value)?
+                    } else {
+                        value
+                            .parse::<u8>()
+                            .ok()
+                            .and_then(|id| id.try_into().ok())
+                            .ok_or_else(|| format!("unknown provider ID \"{}\"", value))?
+                    };
+                    if provider.replace(id).is_some() {
+                        return Err(format!("the provider is given twice in \"{}\"", input));
+                    }
+                }
+                _ => return Err(format!("unknown key URI attribute \"{}\"", attribute)),
+            }
+        }
+
+        Ok(KeyRef {
+            name: name.ok_or_else(|| format!("no key name in \"{}\"", input))?,
+            provider,
+        })
+    }
+}
+
+// Key references are used as key names by the subcommands.
+impl Deref for KeyRef {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for KeyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+fn parse_provider_name(name: &str) -> Result<ProviderId, String> {
+    match name {
+        "mbed-crypto" => Ok(ProviderId::MbedCrypto),
+        "pkcs11" => Ok(ProviderId::Pkcs11),
+        "tpm" => Ok(ProviderId::Tpm),
+        "trusted-service" => Ok(ProviderId::TrustedService),
+        "cryptoauthlib" => Ok(ProviderId::CryptoAuthLib),
+        _ => Err(format!(
+            "unknown provider \"{}\", expected mbed-crypto, pkcs11, tpm, trusted-service or cryptoauthlib",
+            name
+        )),
+    }
+}
+
+fn percent_decode(input: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let value = tail
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("invalid percent-encoding in \"{}\"", input))?;
+            bytes.push(value);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("the key name \"{}\" is not UTF-8", input))
+}
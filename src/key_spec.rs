@@ -0,0 +1,755 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Textual representation of PSA key attributes.
+//!
+//! Key types, algorithms, usage flags and lifetimes can be written as short strings on the
+//! command-line or in key template files, and are printed back in the same form. Algorithms use a
+//! function-like syntax where parameters are given in parentheses, for example `ecdsa(sha384)`,
+//! `rsa-pss(any)`, `hmac(sha256)` or `ecdh(hkdf(sha256))`.
+
+use crate::error::{Result, ToolErrorKind};
+use log::error;
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricEncryption, AsymmetricSignature, Cipher,
+    FullLengthMac, Hash, KeyAgreement, KeyDerivation, Mac, RawKeyAgreement, SignHash,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{
+    Attributes, DhFamily, EccFamily, Lifetime, Policy, Type, UsageFlags,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Names of the usage flags accepted by `parse_usage_flag`.
+pub const USAGE_FLAG_NAMES: [&str; 12] = [
+    "sign",
+    "verify",
+    "sign-hash",
+    "verify-hash",
+    "sign-message",
+    "verify-message",
+    "encrypt",
+    "decrypt",
+    "export",
+    "copy",
+    "cache",
+    "derive",
+];
+
+/// Specification of the attributes of a key, as read from a key template file.
+///
+/// All fields are strings using the syntax of the `parse_*` functions of this module. Missing fields
+/// can be completed from the command-line.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct KeyTemplate {
+    /// Type of the key, e.g. `ecc-key-pair(secp-r1)`.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+    /// Size of the key in bits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bits: Option<usize>,
+    /// Lifetime of the key, `persistent` or `volatile`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lifetime: Option<String>,
+    /// Usage flags of the key, e.g. `["sign", "verify"]`.
+    #[serde(default)]
+    pub usage: Vec<String>,
+    /// Permitted algorithm of the key, e.g. `ecdsa(sha256)`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+}
+
+impl KeyTemplate {
+    /// Reads a template from a TOML file, or a JSON file if the file name ends with `.json`.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let template = if path.extension().map_or(false, |ext| ext == "json") {
+            serde_json::from_str(&contents).map_err(|e| {
+                error!("Could not parse the JSON key template: {}", e);
+                ToolErrorKind::IncorrectData
+            })?
+        } else {
+            toml::from_str(&contents).map_err(|e| {
+                error!("Could not parse the TOML key template: {}", e);
+                ToolErrorKind::IncorrectData
+            })?
+        };
+        Ok(template)
+    }
+
+    /// Creates a template describing the given attributes. This is the inverse of `to_attributes`.
+    pub fn from_attributes(attributes: &Attributes) -> Self {
+        KeyTemplate {
+            key_type: Some(key_type_to_string(attributes.key_type)),
+            bits: Some(attributes.bits),
+            lifetime: Some(lifetime_to_string(attributes.lifetime)),
+            usage: usage_flags_to_strings(attributes.policy.usage_flags)
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            algorithm: Some(algorithm_to_string(attributes.policy.permitted_algorithms)),
+        }
+    }
+
+    /// Converts the template into key attributes.
+    ///
+    /// The key type and the permitted algorithm are mandatory. If the size of the key is not given,
+    /// the default size of the key type is used.
+    pub fn to_attributes(&self) -> Result<Attributes> {
+        let key_type = parse_key_type(self.key_type.as_deref().ok_or_else(|| {
+            error!("The type of the key was not specified");
+            ToolErrorKind::NoInput
+        })?)?;
+
+        let bits = match self.bits.or_else(|| default_bits(key_type)) {
+            Some(bits) => bits,
+            None => {
+                error!("The size of the key was not specified");
+                return Err(ToolErrorKind::NoInput.into());
+            }
+        };
+
+        let lifetime = match &self.lifetime {
+            Some(lifetime) => parse_lifetime(lifetime)?,
+            None => Lifetime::Persistent,
+        };
+
+        let mut usage_flags = UsageFlags::default();
+        for flag in &self.usage {
+            parse_usage_flag(flag, &mut usage_flags)?;
+        }
+
+        let permitted_algorithms =
+            parse_algorithm(self.algorithm.as_deref().ok_or_else(|| {
+                error!("The permitted algorithm of the key was not specified");
+                ToolErrorKind::NoInput
+            })?)?;
+
+        Ok(Attributes {
+            lifetime,
+            key_type,
+            bits,
+            policy: Policy {
+                usage_flags,
+                permitted_algorithms,
+            },
+        })
+    }
+}
+
+/// Returns the size used for a key type when none is specified, if there is a sensible default.
+pub fn default_bits(key_type: Type) -> Option<usize> {
+    match key_type {
+        Type::RsaKeyPair => Some(2048),
+        Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        } => Some(256),
+        Type::EccKeyPair {
+            curve_family: EccFamily::Montgomery,
+        } => Some(255),
+        Type::Aes | Type::Chacha20 => Some(256),
+        _ => None,
+    }
+}
+
+/// Parses a key type, e.g. `rsa-key-pair` or `ecc-key-pair(secp-r1)`.
+pub fn parse_key_type(input: &str) -> Result<Type> {
+    let (name, args) = split_call(input)?;
+    let key_type = match (name.as_str(), args.as_slice()) {
+        ("raw-data", []) => Type::RawData,
+        ("hmac", []) => Type::Hmac,
+        ("derive", []) => Type::Derive,
+        ("aes", []) => Type::Aes,
+        ("des", []) => Type::Des,
+        ("camellia", []) => Type::Camellia,
+        ("arc4", []) => Type::Arc4,
+        ("chacha20", []) => Type::Chacha20,
+        ("rsa-public-key", []) => Type::RsaPublicKey,
+        ("rsa-key-pair", []) => Type::RsaKeyPair,
+        ("ecc-key-pair", [family]) => Type::EccKeyPair {
+            curve_family: parse_ecc_family(family)?,
+        },
+        ("ecc-public-key", [family]) => Type::EccPublicKey {
+            curve_family: parse_ecc_family(family)?,
+        },
+        ("dh-key-pair", [family]) => Type::DhKeyPair {
+            group_family: parse_dh_family(family)?,
+        },
+        ("dh-public-key", [family]) => Type::DhPublicKey {
+            group_family: parse_dh_family(family)?,
+        },
+        _ => {
+            error!("Unknown key type \"{}\"", input);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    Ok(key_type)
+}
+
+/// Formats a key type using the syntax of `parse_key_type`.
+pub fn key_type_to_string(key_type: Type) -> String {
+    match key_type {
+        Type::RawData => String::from("raw-data"),
+        Type::Hmac => String::from("hmac"),
+        Type::Derive => String::from("derive"),
+        Type::Aes => String::from("aes"),
+        Type::Des => String::from("des"),
+        Type::Camellia => String::from("camellia"),
+        Type::Arc4 => String::from("arc4"),
+        Type::Chacha20 => String::from("chacha20"),
+        Type::RsaPublicKey => String::from("rsa-public-key"),
+        Type::RsaKeyPair => String::from("rsa-key-pair"),
+        Type::EccKeyPair { curve_family } => {
+            format!("ecc-key-pair({})", ecc_family_to_str(curve_family))
+        }
+        Type::EccPublicKey { curve_family } => {
+            format!("ecc-public-key({})", ecc_family_to_str(curve_family))
+        }
+        Type::DhKeyPair { group_family } => {
+            format!("dh-key-pair({})", dh_family_to_str(group_family))
+        }
+        Type::DhPublicKey { group_family } => {
+            format!("dh-public-key({})", dh_family_to_str(group_family))
+        }
+    }
+}
+
+/// Parses an ECC curve family, e.g. `secp-r1` or `montgomery`.
+#[allow(deprecated)]
+pub fn parse_ecc_family(input: &str) -> Result<EccFamily> {
+    let family = match input.trim().to_lowercase().as_str() {
+        "secp-k1" => EccFamily::SecpK1,
+        "secp-r1" => EccFamily::SecpR1,
+        "secp-r2" => EccFamily::SecpR2,
+        "sect-k1" => EccFamily::SectK1,
+        "sect-r1" => EccFamily::SectR1,
+        "sect-r2" => EccFamily::SectR2,
+        "brainpool-p-r1" => EccFamily::BrainpoolPR1,
+        "frp" => EccFamily::Frp,
+        "montgomery" => EccFamily::Montgomery,
+        _ => {
+            error!("Unknown ECC family \"{}\"", input);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    Ok(family)
+}
+
+/// Parses a named curve, e.g. `P-384`, `secp256k1` or `brainpoolP256r1`, into its ECC family and size.
+///
+/// Both the SEC names and the NIST names are accepted, case-insensitively.
+pub fn parse_curve(input: &str) -> Result<(EccFamily, usize)> {
+    let curve = match input.trim().to_lowercase().as_str() {
+        "p-192" | "secp192r1" | "prime192v1" => (EccFamily::SecpR1, 192),
+        "p-224" | "secp224r1" => (EccFamily::SecpR1, 224),
+        "p-256" | "secp256r1" | "prime256v1" => (EccFamily::SecpR1, 256),
+        "p-384" | "secp384r1" => (EccFamily::SecpR1, 384),
+        "p-521" | "secp521r1" => (EccFamily::SecpR1, 521),
+        "secp192k1" => (EccFamily::SecpK1, 192),
+        "secp224k1" => (EccFamily::SecpK1, 224),
+        "secp256k1" => (EccFamily::SecpK1, 256),
+        "k-233" | "sect233k1" => (EccFamily::SectK1, 233),
+        "k-283" | "sect283k1" => (EccFamily::SectK1, 283),
+        "k-409" | "sect409k1" => (EccFamily::SectK1, 409),
+        "k-571" | "sect571k1" => (EccFamily::SectK1, 571),
+        "b-233" | "sect233r1" => (EccFamily::SectR1, 233),
+        "b-283" | "sect283r1" => (EccFamily::SectR1, 283),
+        "b-409" | "sect409r1" => (EccFamily::SectR1, 409),
+        "b-571" | "sect571r1" => (EccFamily::SectR1, 571),
+        "brainpoolp160r1" => (EccFamily::BrainpoolPR1, 160),
+        "brainpoolp192r1" => (EccFamily::BrainpoolPR1, 192),
+        "brainpoolp224r1" => (EccFamily::BrainpoolPR1, 224),
+        "brainpoolp256r1" => (EccFamily::BrainpoolPR1, 256),
+        "brainpoolp320r1" => (EccFamily::BrainpoolPR1, 320),
+        "brainpoolp384r1" => (EccFamily::BrainpoolPR1, 384),
+        "brainpoolp512r1" => (EccFamily::BrainpoolPR1, 512),
+        "curve25519" | "x25519" => (EccFamily::Montgomery, 255),
+        "curve448" | "x448" => (EccFamily::Montgomery, 448),
+        _ => {
+            error!("Unknown named curve \"{}\"", input);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    Ok(curve)
+}
+
+/// Returns the hash algorithm matching the strength of an ECC curve of the given size.
+pub fn default_ecdsa_hash(bits: usize) -> Hash {
+    match bits {
+        0..=256 => Hash::Sha256,
+        257..=384 => Hash::Sha384,
+        _ => Hash::Sha512,
+    }
+}
+
+#[allow(deprecated)]
+fn ecc_family_to_str(family: EccFamily) -> &'static str {
+    match family {
+        EccFamily::SecpK1 => "secp-k1",
+        EccFamily::SecpR1 => "secp-r1",
+        EccFamily::SecpR2 => "secp-r2",
+        EccFamily::SectK1 => "sect-k1",
+        EccFamily::SectR1 => "sect-r1",
+        EccFamily::SectR2 => "sect-r2",
+        EccFamily::BrainpoolPR1 => "brainpool-p-r1",
+        EccFamily::Frp => "frp",
+        EccFamily::Montgomery => "montgomery",
+    }
+}
+
+fn parse_dh_family(input: &str) -> Result<DhFamily> {
+    match input.trim().to_lowercase().as_str() {
+        "rfc7919" => Ok(DhFamily::Rfc7919),
+        _ => {
+            error!("Unknown Diffie-Hellman family \"{}\"", input);
+            Err(ToolErrorKind::IncorrectData.into())
+        }
+    }
+}
+
+fn dh_family_to_str(family: DhFamily) -> &'static str {
+    match family {
+        DhFamily::Rfc7919 => "rfc7919",
+    }
+}
+
+/// Parses a hash algorithm, e.g. `sha256`, `sha-256` or `sha3-256`.
+pub fn parse_hash(input: &str) -> Result<Hash> {
+    lookup_hash(input).ok_or_else(|| {
+        error!("Unknown hash algorithm \"{}\"", input);
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+#[allow(deprecated)]
+fn lookup_hash(input: &str) -> Option<Hash> {
+    let normalised: String = input
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .collect();
+    let hash = match normalised.as_str() {
+        "md2" => Hash::Md2,
+        "md4" => Hash::Md4,
+        "md5" => Hash::Md5,
+        "ripemd160" => Hash::Ripemd160,
+        "sha1" => Hash::Sha1,
+        "sha224" => Hash::Sha224,
+        "sha256" => Hash::Sha256,
+        "sha384" => Hash::Sha384,
+        "sha512" => Hash::Sha512,
+        "sha512224" => Hash::Sha512_224,
+        "sha512256" => Hash::Sha512_256,
+        "sha3224" => Hash::Sha3_224,
+        "sha3256" => Hash::Sha3_256,
+        "sha3384" => Hash::Sha3_384,
+        "sha3512" => Hash::Sha3_512,
+        _ => return None,
+    };
+    Some(hash)
+}
+
+/// Formats a hash algorithm using the syntax of `parse_hash`.
+#[allow(deprecated)]
+pub fn hash_to_str(hash: Hash) -> &'static str {
+    match hash {
+        Hash::Md2 => "md2",
+        Hash::Md4 => "md4",
+        Hash::Md5 => "md5",
+        Hash::Ripemd160 => "ripemd160",
+        Hash::Sha1 => "sha1",
+        Hash::Sha224 => "sha224",
+        Hash::Sha256 => "sha256",
+        Hash::Sha384 => "sha384",
+        Hash::Sha512 => "sha512",
+        Hash::Sha512_224 => "sha512-224",
+        Hash::Sha512_256 => "sha512-256",
+        Hash::Sha3_224 => "sha3-224",
+        Hash::Sha3_256 => "sha3-256",
+        Hash::Sha3_384 => "sha3-384",
+        Hash::Sha3_512 => "sha3-512",
+    }
+}
+
+/// Parses the hash of a hash-and-sign algorithm: a hash algorithm, or `any`.
+pub fn parse_sign_hash(input: &str) -> Result<SignHash> {
+    if input.trim().eq_ignore_ascii_case("any") {
+        Ok(SignHash::Any)
+    } else {
+        Ok(SignHash::Specific(parse_hash(input)?))
+    }
+}
+
+fn sign_hash_to_str(hash: SignHash) -> &'static str {
+    match hash {
+        SignHash::Specific(hash) => hash_to_str(hash),
+        SignHash::Any => "any",
+    }
+}
+
+/// Parses an algorithm, e.g. `ecdsa(sha256)`, `rsa-oaep(sha1)`, `gcm` or `hmac(sha384)`.
+///
+/// MAC and AEAD algorithms take an optional length (in bytes) as a last parameter to truncate the
+/// MAC or the tag, e.g. `hmac(sha256,16)` or `ccm(8)`.
+pub fn parse_algorithm(input: &str) -> Result<Algorithm> {
+    let (name, args) = split_call(input)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let algorithm = match (name.as_str(), args.as_slice()) {
+        ("none", []) => Algorithm::None,
+        // Asymmetric signature
+        ("rsa-pkcs1v15-sign", [hash]) => AsymmetricSignature::RsaPkcs1v15Sign {
+            hash_alg: parse_sign_hash(hash)?,
+        }
+        .into(),
+        ("rsa-pkcs1v15-sign-raw", []) => AsymmetricSignature::RsaPkcs1v15SignRaw.into(),
+        ("rsa-pss", [hash]) => AsymmetricSignature::RsaPss {
+            hash_alg: parse_sign_hash(hash)?,
+        }
+        .into(),
+        ("ecdsa", [hash]) => AsymmetricSignature::Ecdsa {
+            hash_alg: parse_sign_hash(hash)?,
+        }
+        .into(),
+        ("ecdsa-any", []) => AsymmetricSignature::EcdsaAny.into(),
+        ("deterministic-ecdsa", [hash]) => AsymmetricSignature::DeterministicEcdsa {
+            hash_alg: parse_sign_hash(hash)?,
+        }
+        .into(),
+        // Asymmetric encryption
+        ("rsa-pkcs1v15-crypt", []) => AsymmetricEncryption::RsaPkcs1v15Crypt.into(),
+        ("rsa-oaep", [hash]) => AsymmetricEncryption::RsaOaep {
+            hash_alg: parse_hash(hash)?,
+        }
+        .into(),
+        // Unauthenticated ciphers
+        ("stream-cipher", []) => Cipher::StreamCipher.into(),
+        ("ctr", []) => Cipher::Ctr.into(),
+        ("cfb", []) => Cipher::Cfb.into(),
+        ("ofb", []) => Cipher::Ofb.into(),
+        ("xts", []) => Cipher::Xts.into(),
+        ("ecb-no-padding", []) => Cipher::EcbNoPadding.into(),
+        ("cbc-no-padding", []) => Cipher::CbcNoPadding.into(),
+        ("cbc-pkcs7", []) => Cipher::CbcPkcs7.into(),
+        // AEAD
+        ("ccm", args) | ("gcm", args) | ("chacha20-poly1305", args) if args.len() <= 1 => {
+            let aead_alg = match name.as_str() {
+                "ccm" => AeadWithDefaultLengthTag::Ccm,
+                "gcm" => AeadWithDefaultLengthTag::Gcm,
+                _ => AeadWithDefaultLengthTag::Chacha20Poly1305,
+            };
+            match args.first() {
+                Some(tag_length) => Aead::AeadWithShortenedTag {
+                    aead_alg,
+                    tag_length: parse_length(tag_length)?,
+                },
+                None => Aead::AeadWithDefaultLengthTag(aead_alg),
+            }
+            .into()
+        }
+        // MAC
+        ("hmac", [hash]) => Mac::FullLength(FullLengthMac::Hmac {
+            hash_alg: parse_hash(hash)?,
+        })
+        .into(),
+        ("hmac", [hash, mac_length]) => Mac::Truncated {
+            mac_alg: FullLengthMac::Hmac {
+                hash_alg: parse_hash(hash)?,
+            },
+            mac_length: parse_length(mac_length)?,
+        }
+        .into(),
+        ("cbc-mac", []) => Mac::FullLength(FullLengthMac::CbcMac).into(),
+        ("cbc-mac", [mac_length]) => Mac::Truncated {
+            mac_alg: FullLengthMac::CbcMac,
+            mac_length: parse_length(mac_length)?,
+        }
+        .into(),
+        ("cmac", []) => Mac::FullLength(FullLengthMac::Cmac).into(),
+        ("cmac", [mac_length]) => Mac::Truncated {
+            mac_alg: FullLengthMac::Cmac,
+            mac_length: parse_length(mac_length)?,
+        }
+        .into(),
+        // Key agreement
+        ("ecdh", []) | ("ffdh", []) => KeyAgreement::Raw(parse_raw_key_agreement(&name)?).into(),
+        ("ecdh", [kdf]) | ("ffdh", [kdf]) => KeyAgreement::WithKeyDerivation {
+            ka_alg: parse_raw_key_agreement(&name)?,
+            kdf_alg: parse_key_derivation(kdf)?,
+        }
+        .into(),
+        // Key derivation
+        ("hkdf", [_]) | ("tls12-prf", [_]) | ("tls12-psk-to-ms", [_]) => {
+            parse_key_derivation(input)?.into()
+        }
+        // Hash
+        (_, []) if lookup_hash(&name).is_some() => parse_hash(&name)?.into(),
+        _ => {
+            error!("Unknown algorithm \"{}\"", input);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    Ok(algorithm)
+}
+
+/// Formats an algorithm using the syntax of `parse_algorithm`.
+pub fn algorithm_to_string(algorithm: Algorithm) -> String {
+    match algorithm {
+        Algorithm::None => String::from("none"),
+        Algorithm::Hash(hash) => String::from(hash_to_str(hash)),
+        Algorithm::Mac(mac) => match mac {
+            Mac::FullLength(mac_alg) => full_length_mac_to_string(mac_alg, None),
+            Mac::Truncated {
+                mac_alg,
+                mac_length,
+            } => full_length_mac_to_string(mac_alg, Some(mac_length)),
+        },
+        Algorithm::Cipher(cipher) => String::from(match cipher {
+            Cipher::StreamCipher => "stream-cipher",
+            Cipher::Ctr => "ctr",
+            Cipher::Cfb => "cfb",
+            Cipher::Ofb => "ofb",
+            Cipher::Xts => "xts",
+            Cipher::EcbNoPadding => "ecb-no-padding",
+            Cipher::CbcNoPadding => "cbc-no-padding",
+            Cipher::CbcPkcs7 => "cbc-pkcs7",
+        }),
+        Algorithm::Aead(aead) => {
+            let (aead_alg, tag_length) = match aead {
+                Aead::AeadWithDefaultLengthTag(aead_alg) => (aead_alg, None),
+                Aead::AeadWithShortenedTag {
+                    aead_alg,
+                    tag_length,
+                } => (aead_alg, Some(tag_length)),
+            };
+            let name = match aead_alg {
+                AeadWithDefaultLengthTag::Ccm => "ccm",
+                AeadWithDefaultLengthTag::Gcm => "gcm",
+                AeadWithDefaultLengthTag::Chacha20Poly1305 => "chacha20-poly1305",
+            };
+            match tag_length {
+                Some(tag_length) => format!("{}({})", name, tag_length),
+                None => String::from(name),
+            }
+        }
+        Algorithm::AsymmetricSignature(alg) => match alg {
+            AsymmetricSignature::RsaPkcs1v15Sign { hash_alg } => {
+                format!("rsa-pkcs1v15-sign({})", sign_hash_to_str(hash_alg))
+            }
+            AsymmetricSignature::RsaPkcs1v15SignRaw => String::from("rsa-pkcs1v15-sign-raw"),
+            AsymmetricSignature::RsaPss { hash_alg } => {
+                format!("rsa-pss({})", sign_hash_to_str(hash_alg))
+            }
+            AsymmetricSignature::Ecdsa { hash_alg } => {
+                format!("ecdsa({})", sign_hash_to_str(hash_alg))
+            }
+            AsymmetricSignature::EcdsaAny => String::from("ecdsa-any"),
+            AsymmetricSignature::DeterministicEcdsa { hash_alg } => {
+                format!("deterministic-ecdsa({})", sign_hash_to_str(hash_alg))
+            }
+        },
+        Algorithm::AsymmetricEncryption(alg) => match alg {
+            AsymmetricEncryption::RsaPkcs1v15Crypt => String::from("rsa-pkcs1v15-crypt"),
+            AsymmetricEncryption::RsaOaep { hash_alg } => {
+                format!("rsa-oaep({})", hash_to_str(hash_alg))
+            }
+        },
+        Algorithm::KeyAgreement(alg) => match alg {
+            KeyAgreement::Raw(ka_alg) => String::from(raw_key_agreement_to_str(ka_alg)),
+            KeyAgreement::WithKeyDerivation { ka_alg, kdf_alg } => format!(
+                "{}({})",
+                raw_key_agreement_to_str(ka_alg),
+                key_derivation_to_string(kdf_alg)
+            ),
+        },
+        Algorithm::KeyDerivation(alg) => key_derivation_to_string(alg),
+    }
+}
+
+fn full_length_mac_to_string(mac_alg: FullLengthMac, mac_length: Option<usize>) -> String {
+    match (mac_alg, mac_length) {
+        (FullLengthMac::Hmac { hash_alg }, None) => format!("hmac({})", hash_to_str(hash_alg)),
+        (FullLengthMac::Hmac { hash_alg }, Some(length)) => {
+            format!("hmac({},{})", hash_to_str(hash_alg), length)
+        }
+        (FullLengthMac::CbcMac, None) => String::from("cbc-mac"),
+        (FullLengthMac::CbcMac, Some(length)) => format!("cbc-mac({})", length),
+        (FullLengthMac::Cmac, None) => String::from("cmac"),
+        (FullLengthMac::Cmac, Some(length)) => format!("cmac({})", length),
+    }
+}
+
+fn parse_raw_key_agreement(input: &str) -> Result<RawKeyAgreement> {
+    match input {
+        "ecdh" => Ok(RawKeyAgreement::Ecdh),
+        "ffdh" => Ok(RawKeyAgreement::Ffdh),
+        _ => {
+            error!("Unknown key agreement algorithm \"{}\"", input);
+            Err(ToolErrorKind::IncorrectData.into())
+        }
+    }
+}
+
+fn raw_key_agreement_to_str(alg: RawKeyAgreement) -> &'static str {
+    match alg {
+        RawKeyAgreement::Ecdh => "ecdh",
+        RawKeyAgreement::Ffdh => "ffdh",
+    }
+}
+
+fn parse_key_derivation(input: &str) -> Result<KeyDerivation> {
+    let (name, args) = split_call(input)?;
+    let kdf = match (name.as_str(), args.as_slice()) {
+        ("hkdf", [hash]) => KeyDerivation::Hkdf {
+            hash_alg: parse_hash(hash)?,
+        },
+        ("tls12-prf", [hash]) => KeyDerivation::Tls12Prf {
+            hash_alg: parse_hash(hash)?,
+        },
+        ("tls12-psk-to-ms", [hash]) => KeyDerivation::Tls12PskToMs {
+            hash_alg: parse_hash(hash)?,
+        },
+        _ => {
+            error!("Unknown key derivation algorithm \"{}\"", input);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    Ok(kdf)
+}
+
+fn key_derivation_to_string(alg: KeyDerivation) -> String {
+    match alg {
+        KeyDerivation::Hkdf { hash_alg } => format!("hkdf({})", hash_to_str(hash_alg)),
+        KeyDerivation::Tls12Prf { hash_alg } => format!("tls12-prf({})", hash_to_str(hash_alg)),
+        KeyDerivation::Tls12PskToMs { hash_alg } => {
+            format!("tls12-psk-to-ms({})", hash_to_str(hash_alg))
+        }
+    }
+}
+
+/// Sets the usage flag named `input` in `usage_flags`. See `USAGE_FLAG_NAMES` for the accepted
+/// names: `sign` and `verify` set both the hash and the message variants of the flag.
+pub fn parse_usage_flag(input: &str, usage_flags: &mut UsageFlags) -> Result<()> {
+    let _ = match input.trim().to_lowercase().as_str() {
+        "sign" => usage_flags.set_sign_hash().set_sign_message(),
+        "verify" => usage_flags.set_verify_hash().set_verify_message(),
+        "sign-hash" => usage_flags.set_sign_hash(),
+        "verify-hash" => usage_flags.set_verify_hash(),
+        "sign-message" => usage_flags.set_sign_message(),
+        "verify-message" => usage_flags.set_verify_message(),
+        "encrypt" => usage_flags.set_encrypt(),
+        "decrypt" => usage_flags.set_decrypt(),
+        "export" => usage_flags.set_export(),
+        "copy" => usage_flags.set_copy(),
+        "cache" => usage_flags.set_cache(),
+        "derive" => usage_flags.set_derive(),
+        _ => {
+            error!(
+                "Unknown usage flag \"{}\" (expected one of: {})",
+                input,
+                USAGE_FLAG_NAMES.join(", ")
+            );
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+    };
+    Ok(())
+}
+
+/// Lists the names of the usage flags that are set.
+pub fn usage_flags_to_strings(usage_flags: UsageFlags) -> Vec<&'static str> {
+    let flags = [
+        (usage_flags.sign_hash(), "sign-hash"),
+        (usage_flags.verify_hash(), "verify-hash"),
+        (usage_flags.sign_message(), "sign-message"),
+        (usage_flags.verify_message(), "verify-message"),
+        (usage_flags.encrypt(), "encrypt"),
+        (usage_flags.decrypt(), "decrypt"),
+        (usage_flags.export(), "export"),
+        (usage_flags.copy(), "copy"),
+        (usage_flags.cache(), "cache"),
+        (usage_flags.derive(), "derive"),
+    ];
+    flags
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Parses a key lifetime: `persistent`, `volatile` or a numerical implementation-specific value.
+pub fn parse_lifetime(input: &str) -> Result<Lifetime> {
+    match input.trim().to_lowercase().as_str() {
+        "persistent" => Ok(Lifetime::Persistent),
+        "volatile" => Ok(Lifetime::Volatile),
+        other => other.parse().map(Lifetime::Custom).map_err(|_| {
+            error!("Unknown lifetime \"{}\"", input);
+            ToolErrorKind::IncorrectData.into()
+        }),
+    }
+}
+
+/// Formats a key lifetime using the syntax of `parse_lifetime`.
+pub fn lifetime_to_string(lifetime: Lifetime) -> String {
+    match lifetime {
+        Lifetime::Persistent => String::from("persistent"),
+        Lifetime::Volatile => String::from("volatile"),
+        Lifetime::Custom(value) => value.to_string(),
+    }
+}
+
+fn parse_length(input: &str) -> Result<usize> {
+    input.trim().parse().map_err(|_| {
+        error!("Invalid length \"{}\"", input);
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+// Splits "name(arg1,arg2)" into its lowercase name and its top-level arguments. Arguments can
+// themselves contain parentheses.
+fn split_call(input: &str) -> Result<(String, Vec<String>)> {
+    let input = input.trim().to_lowercase();
+    let open = match input.find('(') {
+        Some(open) => open,
+        None => return Ok((input, Vec::new())),
+    };
+    if !input.ends_with(')') {
+        error!("Missing closing parenthesis in \"{}\"", input);
+        return Err(ToolErrorKind::IncorrectData.into());
+    }
+
+    let name = input[..open].trim().to_string();
+    let inner = &input[open + 1..input.len() - 1];
+    // An empty argument list, as in `gcm()`, is the same as no argument list.
+    if inner.trim().is_empty() {
+        return Ok((name, Vec::new()));
+    }
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                error!("Unbalanced parentheses in \"{}\"", input);
+                return Err(ToolErrorKind::IncorrectData.into());
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    if depth != 0 {
+        error!("Unbalanced parentheses in \"{}\"", input);
+        return Err(ToolErrorKind::IncorrectData.into());
+    }
+    args.push(current.trim().to_string());
+
+    Ok((name, args))
+}
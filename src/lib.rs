@@ -4,6 +4,8 @@
 //! Source code for the `parsec-tool` project. This is a command-line interface for interacting
 //! with the Parsec service.
 
+// `private_in_public` is not denied anymore: rustc removed it in 1.74, and warns about it, in favour of
+// `private_interfaces` and `private_bounds`, which are unknown to the 1.66 MSRV compiler.
 #![deny(
     nonstandard_style,
     dead_code,
@@ -13,7 +15,6 @@
     overflowing_literals,
     path_statements,
     patterns_in_fns_without_body,
-    private_in_public,
     unconditional_recursion,
     unused,
     unused_allocation,
@@ -32,8 +33,20 @@
 // This one is hard to avoid.
 #![allow(clippy::multiple_crate_versions)]
 
+pub mod audit_policy;
+pub mod ciphertext;
 pub mod cli;
 pub mod common;
 pub mod error;
+pub mod fingerprint;
+pub mod guardrail_policy;
+pub mod key_alias;
+pub mod key_archive;
+pub mod key_filter;
+pub mod key_format;
+pub mod key_manifest;
+pub mod key_metadata;
+pub mod key_ref;
+pub mod key_spec;
 pub mod subcommands;
 pub mod util;
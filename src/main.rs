@@ -5,8 +5,6 @@
 
 use log::error;
 use parsec_tool::cli;
-use parsec_tool::common::PROJECT_NAME;
-use std::convert::TryInto;
 use structopt::StructOpt;
 
 fn main() {
@@ -22,40 +20,11 @@ fn main() {
 
     let matches = cli::ParsecToolApp::from_args();
 
-    let mut client = match matches
-        .subcommand
-        .create_client(Some(PROJECT_NAME.to_string()))
-    {
-        Err(e) => {
-            error!("Error spinning up the BasicClient: {}", e);
-            std::process::exit(1);
+    if let Err(e) = matches.run() {
+        if !e.is_check_result() {
+            error!("Subcommand failed: {} ({:?})", e, e);
         }
-        Ok(client) => client,
-    };
-
-    if let Some(provider) = matches.provider {
-        let provider = match provider.try_into() {
-            Err(_) => {
-                error!("The provider ID entered does not map with an existing provider");
-                std::process::exit(1);
-            }
-            Ok(provider) => provider,
-        };
-        client.set_implicit_provider(provider);
-    }
-
-    if let Some(timeout) = matches.timeout {
-        let timeout = if timeout == 0 {
-            None
-        } else {
-            Some(std::time::Duration::from_secs(timeout.into()))
-        };
-        client.set_timeout(timeout);
-    }
-
-    if let Err(e) = matches.subcommand.run(client) {
-        error!("Subcommand failed: {} ({:?})", e, e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 
     std::process::exit(0);
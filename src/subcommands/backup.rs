@@ -0,0 +1,186 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Backs up exportable keys into an encrypted archive.
+//!
+//! The keys are exported with their attributes and encrypted either under a password or under a
+//! Parsec RSA-OAEP wrapping key. The archive can be restored with `restore`. See the `key_archive`
+//! module for the format of the archive.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_archive::{
+    ArchivedKey, KeyArchive, Protection, CONTENT_KEY_LENGTH, NONCE_LENGTH, SALT_LENGTH,
+};
+use crate::key_ref::KeyRef;
+use crate::key_spec::KeyTemplate;
+use crate::util::read_password;
+use log::{error, info, warn};
+use parsec_client::core::interface::operations::psa_algorithm::{Algorithm, AsymmetricEncryption};
+use parsec_client::core::interface::operations::psa_key_attributes::Attributes;
+use parsec_client::core::interface::requests::ProviderId;
+use parsec_client::BasicClient;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Backs up keys into an encrypted archive.
+#[derive(Debug, StructOpt)]
+pub struct Backup {
+    /// Name of a key to back up. Can be given several times, and the keys can be in different providers.
+    #[structopt(short = "k", long = "key-name", required_unless = "all")]
+    key_names: Vec<KeyRef>,
+
+    /// Back up all the exportable keys of the provider.
+    #[structopt(long = "all", conflicts_with = "key-names")]
+    all: bool,
+
+    /// Path of the archive to write.
+    #[structopt(short = "o", long = "output-file", parse(from_os_str))]
+    output_file: PathBuf,
+
+    /// Encrypt the archive with a key derived from this password (Argon2id and AES-256-GCM).
+    /// On the command line, the password is visible to the other users of the system and is
+    /// kept in the shell history: prefer --password-file.
+    #[structopt(
+        long = "password",
+        required_unless_one = &["password-file", "wrapping-key"]
+    )]
+    password: Option<String>,
+
+    /// Encrypt the archive with a key derived from the password on the first line of this file.
+    #[structopt(
+        long = "password-file",
+        parse(from_os_str),
+        conflicts_with = "password"
+    )]
+    password_file: Option<PathBuf>,
+
+    /// Encrypt the archive with a random AES-256-GCM key, itself encrypted with this Parsec RSA-OAEP key.
+    #[structopt(long = "wrapping-key", conflicts_with_all = &["password", "password-file"])]
+    wrapping_key: Option<KeyRef>,
+}
+
+impl Backup {
+    /// Backs up keys.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let policy = GuardrailPolicy::load()?;
+        for key_provider in self
+            .key_names
+            .iter()
+            .chain(self.wrapping_key.as_ref())
+            .filter_map(|key| key.provider)
+        {
+            policy.check_provider(key_provider)?;
+        }
+
+        let provider = basic_client.implicit_provider();
+        let available: Vec<(String, ProviderId, Attributes)> = basic_client
+            .list_keys()?
+            .into_iter()
+            .map(|key| (key.name, key.provider_id, key.attributes))
+            .collect();
+
+        let selected: Vec<&(String, ProviderId, Attributes)> = if self.all {
+            available
+                .iter()
+                .filter(|(name, key_provider, attributes)| {
+                    if *key_provider != provider {
+                        return false;
+                    }
+                    if !attributes.is_exportable() {
+                        warn!("Skipping key \"{}\" which is not exportable", name);
+                    }
+                    attributes.is_exportable()
+                })
+                .collect()
+        } else {
+            let mut selected = Vec::new();
+            for key_name in &self.key_names {
+                let key_provider = key_name.provider.unwrap_or(provider);
+                let key = available
+                    .iter()
+                    .find(|(name, id, _)| *name == **key_name && *id == key_provider)
+                    .ok_or_else(|| {
+                        error!("Key \"{}\" does not exist in {}", key_name, key_provider);
+                        ToolErrorKind::IncorrectData
+                    })?;
+                if !key.2.is_exportable() {
+                    error!(
+                        "Key \"{}\" was not created with the export usage flag and cannot be backed up",
+                        key_name
+                    );
+                    return Err(ToolErrorKind::NotExportable.into());
+                }
+                selected.push(key);
+            }
+            selected
+        };
+
+        let mut keys = Vec::new();
+        for (name, key_provider, attributes) in selected {
+            info!("Exporting key \"{}\"...", name);
+            basic_client.set_implicit_provider(*key_provider);
+            keys.push(ArchivedKey {
+                name: name.clone(),
+                provider_id: *key_provider as u8,
+                attributes: KeyTemplate::from_attributes(attributes),
+                data: base64::encode(basic_client.psa_export_key(name)?),
+            });
+        }
+        basic_client.set_implicit_provider(provider);
+
+        let password = read_password(self.password.as_deref(), self.password_file.as_deref())?;
+        let (protection, content_key) = match (password, &self.wrapping_key) {
+            (Some(password), _) => {
+                let protection =
+                    Protection::new_argon2id(&basic_client.psa_generate_random(SALT_LENGTH)?);
+                let content_key = protection.derive_key(&password)?;
+                (protection, content_key)
+            }
+            (None, Some(wrapping_key)) => {
+                if let Some(wrapping_provider) = wrapping_key.provider {
+                    basic_client.set_implicit_provider(wrapping_provider);
+                }
+                let alg = match basic_client
+                    .key_attributes(wrapping_key)?
+                    .policy
+                    .permitted_algorithms
+                {
+                    Algorithm::AsymmetricEncryption(alg @ AsymmetricEncryption::RsaOaep { .. }) => {
+                        alg
+                    }
+                    other => {
+                        error!(
+                            "Wrapping key's algorithm is {:?}, RSA OAEP is required",
+                            other
+                        );
+                        return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                    }
+                };
+                let content_key = basic_client.psa_generate_random(CONTENT_KEY_LENGTH)?;
+                let encrypted_key =
+                    basic_client.psa_asymmetric_encrypt(wrapping_key, alg, &content_key, None)?;
+                basic_client.set_implicit_provider(provider);
+                let protection = Protection::WrappingKey {
+                    name: wrapping_key.name.clone(),
+                    encrypted_key: base64::encode(encrypted_key),
+                };
+                (protection, content_key)
+            }
+            (None, None) => {
+                error!("Either a password or a wrapping key is needed to encrypt the archive");
+                return Err(ToolErrorKind::NoInput.into());
+            }
+        };
+
+        let nonce = basic_client.psa_generate_random(NONCE_LENGTH)?;
+        KeyArchive::seal(&keys, protection, &content_key, &nonce)?.write(&self.output_file)?;
+
+        info!(
+            "{} key(s) backed up to \"{}\".",
+            keys.len(),
+            self.output_file.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs a sequence of subcommands within a single parsec-tool process.
+//!
+//! Commands are read one per line, from a file or from the standard input, and use the same syntax as
+//! the command-line, without the program name. Empty lines and lines starting with `#` are ignored.
+//! The global options given to `batch` apply to every command, unless a command overrides them:
+//!
+//! ```text
+//! create-ecc-key --key-name session-key --volatile
+//! sign --key-name session-key "Hello, Parsec"
+//! -p 3 list-keys
+//! ```
+//!
+//! Keys created with `--volatile` can be used by the following commands of the session. The session
+//! stops at the first command which fails.
+
+use crate::cli::ParsecToolApp;
+use crate::common::PROJECT_NAME;
+use crate::error::{Result, ToolErrorKind};
+use log::{error, info};
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use structopt::StructOpt;
+
+static IN_SESSION: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if the current command is run within a batch session.
+pub fn in_session() -> bool {
+    IN_SESSION.load(Ordering::Relaxed)
+}
+
+/// Runs a sequence of subcommands.
+#[derive(Debug, StructOpt)]
+pub struct Batch {
+    /// File containing the commands, one per line. They are read from the standard input if not given.
+    #[structopt(short = "i", long = "input-file", parse(from_os_str))]
+    input_file: Option<PathBuf>,
+}
+
+impl Batch {
+    /// Runs the commands of the batch session.
+    pub fn run(&self, app: &ParsecToolApp) -> Result<()> {
+        if IN_SESSION.swap(true, Ordering::Relaxed) {
+            error!("Batch sessions can not be nested");
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+
+        let reader: Box<dyn BufRead> = match &self.input_file {
+            Some(path) => Box::new(std::io::BufReader::new(std::fs::File::open(path)?)),
+            None => Box::new(std::io::BufReader::new(std::io::stdin())),
+        };
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            info!("Running \"{}\"...", line);
+            self.run_line(app, line).map_err(|e| {
+                error!("Command at line {} failed", index + 1);
+                e
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn run_line(&self, app: &ParsecToolApp, line: &str) -> Result<()> {
+        let words = shell_words::split(line).map_err(|e| {
+            error!("Could not split the command into arguments: {}", e);
+            ToolErrorKind::IncorrectData
+        })?;
+        let mut command =
+            ParsecToolApp::from_iter_safe(std::iter::once(PROJECT_NAME.to_string()).chain(words))
+                .map_err(|e| {
+                error!("{}", e.message);
+                ToolErrorKind::IncorrectData
+            })?;
+        command.provider = command.provider.or(app.provider);
+        command.timeout = command.timeout.or(app.timeout);
+
+        command.run()
+    }
+}
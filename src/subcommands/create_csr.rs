@@ -4,6 +4,8 @@
 //! Creates a Certificate Signing Request (CSR) from a keypair.
 
 use crate::error::{Error, Result, ToolErrorKind};
+use crate::key_alias::resolve_key_name;
+use crate::key_ref::KeyRef;
 use crate::util::sign_message_with_policy;
 use log::error;
 use parsec_client::core::interface::operations::psa_algorithm::{
@@ -11,11 +13,15 @@ use parsec_client::core::interface::operations::psa_algorithm::{
 };
 use parsec_client::core::interface::operations::psa_key_attributes::{EccFamily, Type};
 use parsec_client::BasicClient;
+use picky_asn1::wrapper::BitStringAsn1;
+use picky_asn1_der::Asn1RawDer;
+use picky_asn1_x509::{AlgorithmIdentifier, HashAlgorithm, RsassaPssParams};
 use rcgen::{
     Certificate, CertificateParams, DistinguishedName, DnType, KeyPair, RcgenError, RemoteKeyPair,
     SignatureAlgorithm, PKCS_ECDSA_P256_SHA256, PKCS_ECDSA_P384_SHA384, PKCS_RSA_SHA256,
     PKCS_RSA_SHA384, PKCS_RSA_SHA512,
 };
+use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 /// Creates an X509 Certificate Signing Request (CSR) from a keypair, using the signing algorithm
@@ -29,7 +35,7 @@ pub struct CreateCsr {
     ///
     /// Elliptic curve keys must use the NIST P256 or P384 curves.
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
 
     /// The common name to be used within the Distinguished Name (DN) specification of
     /// the CSR.
@@ -80,12 +86,13 @@ struct ParsecRemoteKeyPair {
 impl CreateCsr {
     /// Creates a Certificate Signing Request (CSR) from a keypair.
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
-        let public_key = basic_client.psa_export_public_key(&self.key_name)?;
+        let key_name = resolve_key_name(&basic_client, &self.key_name)?;
+        let public_key = basic_client.psa_export_public_key(&key_name)?;
 
-        let rcgen_algorithm = self.get_rcgen_algorithm(&basic_client)?;
+        let (rcgen_algorithm, pss_hash) = self.get_rcgen_algorithm(&basic_client, &key_name)?;
 
         let parsec_key_pair = ParsecRemoteKeyPair {
-            key_name: self.key_name.clone(),
+            key_name,
             public_key_der: public_key,
             // "Move" the client into the struct here.
             parsec_client: basic_client,
@@ -146,7 +153,13 @@ impl CreateCsr {
 
         let cert = Certificate::from_params(params)?;
 
-        let pem_string = cert.serialize_request_pem()?;
+        let pem_string = match pss_hash {
+            Some(hash) => pem::encode(&pem::Pem {
+                tag: String::from("CERTIFICATE REQUEST"),
+                contents: set_rsa_pss_algorithm(&cert.serialize_request_der()?, hash)?,
+            }),
+            None => cert.serialize_request_pem()?,
+        };
 
         println!("{}", pem_string);
 
@@ -154,23 +167,26 @@ impl CreateCsr {
     }
 
     // Inspect the attributes of the signing key and map them down to one of rcgen's supported hash-and-sign
-    // schemes (throwing an error if there isn't a suitable mapping).
+    // schemes (throwing an error if there isn't a suitable mapping). RSA PSS is not supported by rcgen: for
+    // PSS keys, the hash algorithm is also returned, and the signature algorithm of the request has to be
+    // replaced once it is created.
     //
     // There's rather a lot of complexity here, because we need to map down lots of nested PSA properties onto a small number
     // of hash-and-sign schemes that RCGEN supports.
     fn get_rcgen_algorithm(
         &self,
         basic_client: &BasicClient,
-    ) -> Result<&'static SignatureAlgorithm> {
-        let attributes = basic_client.key_attributes(&self.key_name)?;
+        key_name: &str,
+    ) -> Result<(&'static SignatureAlgorithm, Option<Hash>)> {
+        let attributes = basic_client.key_attributes(key_name)?;
 
         if let Algorithm::AsymmetricSignature(alg) = attributes.policy.permitted_algorithms {
             match alg {
                 AsymmetricSignature::RsaPkcs1v15Sign { hash_alg } => match hash_alg {
-                    SignHash::Specific(Hash::Sha256) => Ok(&PKCS_RSA_SHA256),
-                    SignHash::Specific(Hash::Sha384) => Ok(&PKCS_RSA_SHA384),
-                    SignHash::Specific(Hash::Sha512) => Ok(&PKCS_RSA_SHA512),
-                    SignHash::Any => Ok(&PKCS_RSA_SHA256), // Default hash algorithm for the tool.
+                    SignHash::Specific(Hash::Sha256) => Ok((&PKCS_RSA_SHA256, None)),
+                    SignHash::Specific(Hash::Sha384) => Ok((&PKCS_RSA_SHA384, None)),
+                    SignHash::Specific(Hash::Sha512) => Ok((&PKCS_RSA_SHA512, None)),
+                    SignHash::Any => Ok((&PKCS_RSA_SHA256, None)), // Default hash algorithm for the tool.
                     _ => {
                         // The algorithm is specific, but not one that RCGEN can use, so fail the operation.
                         error!("Signing key requires use of hashing algorithm ({:?}), which is not supported for certificate requests.", alg);
@@ -178,14 +194,19 @@ impl CreateCsr {
                     }
                 },
                 AsymmetricSignature::RsaPkcs1v15SignRaw => {
-                    // Key policy specifies raw RSA signatures. RCGEN will always hash-and-sign, so fail.
-                    error!("Signing key specifies raw signing only, which is not supported for certificate requests.");
-                    Err(ToolErrorKind::NotSupported.into())
-                }
-                AsymmetricSignature::RsaPss { .. } => {
-                    error!("Signing key specifies RSA PSS scheme, which is not supported for certificate requests.");
-                    Err(ToolErrorKind::NotSupported.into())
+                    // The DigestInfo is encoded by sign_message_with_policy, with the default hash algorithm.
+                    Ok((&PKCS_RSA_SHA256, None))
                 }
+                AsymmetricSignature::RsaPss { hash_alg } => match hash_alg {
+                    SignHash::Specific(hash @ (Hash::Sha256 | Hash::Sha384 | Hash::Sha512)) => {
+                        Ok((&PKCS_RSA_SHA256, Some(hash)))
+                    }
+                    SignHash::Any => Ok((&PKCS_RSA_SHA256, Some(Hash::Sha256))),
+                    _ => {
+                        error!("Signing key requires use of hashing algorithm ({:?}), which is not supported for certificate requests.", alg);
+                        Err(ToolErrorKind::NotSupported.into())
+                    }
+                },
                 AsymmetricSignature::Ecdsa { hash_alg } => {
                     if !matches!(
                         attributes.key_type,
@@ -202,7 +223,7 @@ impl CreateCsr {
                     match hash_alg {
                         SignHash::Specific(Hash::Sha256) => {
                             if attributes.bits == 256 {
-                                Ok(&PKCS_ECDSA_P256_SHA256)
+                                Ok((&PKCS_ECDSA_P256_SHA256, None))
                             } else {
                                 error!("Signing key should have strength 256, but actually has strength {}.", attributes.bits);
                                 Err(ToolErrorKind::NotSupported.into())
@@ -210,7 +231,7 @@ impl CreateCsr {
                         }
                         SignHash::Specific(Hash::Sha384) => {
                             if attributes.bits == 384 {
-                                Ok(&PKCS_ECDSA_P384_SHA384)
+                                Ok((&PKCS_ECDSA_P384_SHA384, None))
                             } else {
                                 error!("Signing key should have strength 384, but actually has strength {}.", attributes.bits);
                                 Err(ToolErrorKind::NotSupported.into())
@@ -218,7 +239,7 @@ impl CreateCsr {
                         }
                         SignHash::Any => {
                             match attributes.bits {
-                                256 => Ok(&PKCS_ECDSA_P256_SHA256),
+                                256 => Ok((&PKCS_ECDSA_P256_SHA256, None)),
                                 _ => {
                                     // We have to fail this, because ParsecRemoteKeyPair::sign() defaults the hash to SHA-256, and RCGEN
                                     // doesn't support a hash algorithm that is different from the key strength.
@@ -247,6 +268,38 @@ impl CreateCsr {
     }
 }
 
+/// A certification request, with the request information kept as encoded.
+#[derive(Serialize, Deserialize)]
+struct RawCertificationRequest {
+    certification_request_info: Asn1RawDer,
+    signature_algorithm: Asn1RawDer,
+    signature: BitStringAsn1,
+}
+
+// Replaces the signature algorithm of a request signed with RSA PSS by RSASSA-PSS, with the parameters used
+// by the Parsec service: MGF1 with the same hash algorithm, and a salt as long as the hash.
+fn set_rsa_pss_algorithm(request: &[u8], hash: Hash) -> Result<Vec<u8>> {
+    let hash_algorithm = match hash {
+        Hash::Sha256 => HashAlgorithm::SHA256,
+        Hash::Sha384 => HashAlgorithm::SHA384,
+        _ => HashAlgorithm::SHA512,
+    };
+    let mut request: RawCertificationRequest =
+        picky_asn1_der::from_bytes(request).map_err(|e| {
+            error!("Could not parse the certificate request: {}", e);
+            ToolErrorKind::IncorrectData
+        })?;
+    let algorithm = AlgorithmIdentifier::new_rsassa_pss(RsassaPssParams::new(hash_algorithm));
+    request.signature_algorithm = Asn1RawDer(picky_asn1_der::to_vec(&algorithm).map_err(|e| {
+        error!("Could not encode the signature algorithm: {}", e);
+        ToolErrorKind::IncorrectData
+    })?);
+    picky_asn1_der::to_vec(&request).map_err(|e| {
+        error!("Could not encode the certificate request: {}", e);
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
 impl RemoteKeyPair for ParsecRemoteKeyPair {
     fn public_key(&self) -> &[u8] {
         &self.public_key_der
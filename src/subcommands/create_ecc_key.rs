@@ -3,13 +3,20 @@
 
 //! Create an ECC key pair.
 //!
-use crate::error::Result;
-use log::info;
-/// The curve will be secp256r1. Used by default for asymmetric signing with ECDSA (SHA-256).
-use parsec_client::core::interface::operations::psa_algorithm::{AsymmetricSignature, Hash};
-use parsec_client::core::interface::operations::psa_key_attributes::{
-    Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags,
+use crate::error::{Result, ToolErrorKind};
+use crate::key_ref::KeyRef;
+use crate::key_spec::{
+    algorithm_to_string, default_ecdsa_hash, key_type_to_string, parse_curve, parse_sign_hash,
+    KeyTemplate,
 };
+use crate::subcommands::create_key::create_key_from_template;
+use log::error;
+/// The curve will be secp256r1 by default. Used for asymmetric signing with ECDSA, using by default the
+/// hash matching the size of the curve, or for key agreement with ECDH.
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Algorithm, AsymmetricSignature, KeyAgreement, RawKeyAgreement,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{EccFamily, Type};
 use parsec_client::BasicClient;
 use structopt::StructOpt;
 
@@ -17,40 +24,59 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 pub struct CreateEccKey {
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
+
+    /// Named curve of the key: P-256 (default), P-384, P-521, secp256k1, brainpoolP256r1,
+    /// brainpoolP384r1, brainpoolP512r1, sect283k1...
+    #[structopt(short = "c", long = "curve", default_value = "P-256")]
+    curve: String,
+
+    /// Hash algorithm used with ECDSA: sha256, sha384, sha512... or "any" to let the hash be chosen
+    /// when signing. Defaults to the hash matching the size of the curve.
+    #[structopt(long = "hash", conflicts_with = "ecdh")]
+    hash: Option<String>,
+
+    /// Create a key agreement (ECDH) key instead of a signing key. Keys on the Montgomery curves
+    /// (X25519, X448) are always key agreement keys.
+    #[structopt(long = "ecdh")]
+    ecdh: bool,
+
+    /// Create a volatile key, which is not stored persistently by the service.
+    #[structopt(long = "volatile")]
+    volatile: bool,
 }
 
 impl CreateEccKey {
     /// Exports a key.
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
-        info!("Creating ECC signing key...");
-
-        let attributes = Attributes {
-            lifetime: Lifetime::Persistent,
-            key_type: Type::EccKeyPair {
-                curve_family: EccFamily::SecpR1,
-            },
-            bits: 256,
-            policy: Policy {
-                usage_flags: {
-                    let mut usage_flags = UsageFlags::default();
-                    let _ = usage_flags
-                        .set_sign_hash()
-                        .set_sign_message()
-                        .set_verify_hash()
-                        .set_verify_message();
-                    usage_flags
-                },
-                permitted_algorithms: AsymmetricSignature::Ecdsa {
-                    hash_alg: Hash::Sha256.into(),
+        let (curve_family, bits) = parse_curve(&self.curve)?;
+
+        let (usage, algorithm): (&[&str], Algorithm) =
+            if self.ecdh || curve_family == EccFamily::Montgomery {
+                if self.hash.is_some() {
+                    error!("Key agreement keys do not use a hash algorithm");
+                    return Err(ToolErrorKind::WrongKeyAlgorithm.into());
                 }
-                .into(),
-            },
-        };
+                (&["derive"], KeyAgreement::Raw(RawKeyAgreement::Ecdh).into())
+            } else {
+                let hash_alg = match &self.hash {
+                    Some(hash) => parse_sign_hash(hash)?,
+                    None => default_ecdsa_hash(bits).into(),
+                };
+                (
+                    &["sign", "verify"],
+                    AsymmetricSignature::Ecdsa { hash_alg }.into(),
+                )
+            };
 
-        basic_client.psa_generate_key(&self.key_name, attributes)?;
+        let template = KeyTemplate {
+            key_type: Some(key_type_to_string(Type::EccKeyPair { curve_family })),
+            bits: Some(bits),
+            lifetime: self.volatile.then(|| String::from("volatile")),
+            usage: usage.iter().map(|flag| flag.to_string()).collect(),
+            algorithm: Some(algorithm_to_string(algorithm)),
+        };
 
-        info!("Key \"{}\" created.", self.key_name);
-        Ok(())
+        create_key_from_template(&basic_client, &self.key_name, &template)
     }
 }
@@ -0,0 +1,171 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Create a key with a full specification of its attributes.
+//!
+//! The attributes can be given on the command-line, read from a TOML or JSON template file, or both, in
+//! which case the command-line takes precedence. A template looks like:
+//!
+//! ```toml
+//! type = "ecc-key-pair(secp-r1)"
+//! bits = 384
+//! usage = ["sign", "verify"]
+//! algorithm = "ecdsa(sha384)"
+//! ```
+
+use crate::error::Result;
+use crate::key_ref::KeyRef;
+use crate::key_spec::{algorithm_to_string, key_type_to_string, KeyTemplate};
+use crate::subcommands::batch::in_session;
+use log::{info, warn};
+use parsec_client::core::interface::operations::psa_key_attributes::{Attributes, Lifetime};
+use parsec_client::BasicClient;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Create a key with a full specification of its attributes.
+#[derive(Debug, StructOpt)]
+pub struct CreateKey {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// TOML (or JSON, with a .json extension) file containing the attributes of the key. Attributes
+    /// given on the command-line override the ones of the template.
+    #[structopt(long = "template", parse(from_os_str))]
+    template: Option<PathBuf>,
+
+    /// Type of the key: raw-data, hmac, derive, aes, des, camellia, arc4, chacha20, rsa-key-pair,
+    /// rsa-public-key, ecc-key-pair(<family>), ecc-public-key(<family>), dh-key-pair(rfc7919) or
+    /// dh-public-key(rfc7919). ECC families are secp-k1, secp-r1, secp-r2, sect-k1, sect-r1, sect-r2,
+    /// brainpool-p-r1, frp and montgomery.
+    #[structopt(long = "type")]
+    key_type: Option<String>,
+
+    /// Size of the key in bits. Defaults to 2048 for RSA keys, 256 for secp-r1 ECC, AES and ChaCha20
+    /// keys and 255 for Montgomery keys.
+    #[structopt(short = "b", long = "bits")]
+    bits: Option<usize>,
+
+    /// Lifetime of the key: persistent (default) or volatile.
+    #[structopt(long = "lifetime")]
+    lifetime: Option<String>,
+
+    /// Create a volatile key. Same as "--lifetime volatile".
+    #[structopt(long = "volatile", conflicts_with = "lifetime")]
+    volatile: bool,
+
+    /// Permit signing hashes and messages with the key.
+    #[structopt(long = "sign")]
+    sign: bool,
+
+    /// Permit verifying signatures of hashes and messages with the key.
+    #[structopt(long = "verify")]
+    verify: bool,
+
+    /// Permit encrypting with the key.
+    #[structopt(long = "encrypt")]
+    encrypt: bool,
+
+    /// Permit decrypting with the key.
+    #[structopt(long = "decrypt")]
+    decrypt: bool,
+
+    /// Permit exporting the key.
+    #[structopt(long = "export")]
+    export: bool,
+
+    /// Permit copying the key.
+    #[structopt(long = "copy")]
+    copy: bool,
+
+    /// Permit deriving other keys from the key.
+    #[structopt(long = "derive")]
+    derive: bool,
+
+    /// Algorithm permitted by the key policy, e.g. ecdsa(sha384), rsa-pss(sha512), rsa-pkcs1v15-sign(any),
+    /// rsa-oaep(sha256), rsa-pkcs1v15-crypt, gcm, ccm, chacha20-poly1305, cbc-pkcs7, hmac(sha256) or ecdh.
+    #[structopt(short = "a", long = "algorithm")]
+    algorithm: Option<String>,
+}
+
+impl CreateKey {
+    /// Creates a key.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let mut template = match &self.template {
+            Some(path) => KeyTemplate::from_file(path)?,
+            None => KeyTemplate::default(),
+        };
+
+        if self.key_type.is_some() {
+            template.key_type = self.key_type.clone();
+        }
+        if self.bits.is_some() {
+            template.bits = self.bits;
+        }
+        if self.lifetime.is_some() {
+            template.lifetime = self.lifetime.clone();
+        }
+        if self.volatile {
+            template.lifetime = Some(String::from("volatile"));
+        }
+        if self.algorithm.is_some() {
+            template.algorithm = self.algorithm.clone();
+        }
+        let flags = [
+            (self.sign, "sign"),
+            (self.verify, "verify"),
+            (self.encrypt, "encrypt"),
+            (self.decrypt, "decrypt"),
+            (self.export, "export"),
+            (self.copy, "copy"),
+            (self.derive, "derive"),
+        ];
+        for (_, flag) in flags.iter().filter(|(set, _)| *set) {
+            template.usage.push(flag.to_string());
+        }
+
+        create_key_from_template(&basic_client, &self.key_name, &template)
+    }
+}
+
+/// Creates a key from a template. `create-rsa-key` and `create-ecc-key` are presets building a template
+/// from their options.
+pub fn create_key_from_template(
+    basic_client: &BasicClient,
+    key_name: &str,
+    template: &KeyTemplate,
+) -> Result<()> {
+    let attributes = template.to_attributes()?;
+
+    info!(
+        "Creating {} key ({} bits, permitted algorithm: {})...",
+        key_type_to_string(attributes.key_type),
+        attributes.bits,
+        algorithm_to_string(attributes.policy.permitted_algorithms)
+    );
+
+    generate_key(basic_client, key_name, attributes)
+}
+
+/// Generates a key with the given attributes. Shared by all the key creation subcommands.
+pub fn generate_key(
+    basic_client: &BasicClient,
+    key_name: &str,
+    attributes: Attributes,
+) -> Result<()> {
+    warn_if_volatile(key_name, attributes.lifetime);
+    basic_client.psa_generate_key(key_name, attributes)?;
+
+    info!("Key \"{}\" created.", key_name);
+    Ok(())
+}
+
+/// Warns that a volatile key created outside of a batch session can not be used afterwards.
+pub fn warn_if_volatile(key_name: &str, lifetime: Lifetime) {
+    if lifetime == Lifetime::Volatile && !in_session() {
+        warn!(
+            "Key \"{}\" is volatile: it is not stored persistently and might not be usable after this command. Use a batch session to create and use it in one process.",
+            key_name
+        );
+    }
+}
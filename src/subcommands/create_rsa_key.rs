@@ -4,23 +4,30 @@
 //! Create a RSA key pair
 //!
 //! The key will be 2048 bits long. Used by default for asymmetric encryption with RSA PKCS#1 v1.5.
+//! Signing keys use PKCS#1 v1.5 with SHA-256 by default, or RSA PSS or raw PKCS#1 v1.5 signatures with
+//! `--scheme`, and another hash with `--hash`. This is a preset of `create-key`: the options are turned
+//! into a key template.
 
-use crate::error::Result;
-use log::info;
-use parsec_client::core::interface::operations::psa_algorithm::{
-    AsymmetricEncryption, AsymmetricSignature, Hash, SignHash,
+use crate::error::{Result, ToolErrorKind};
+use crate::key_ref::KeyRef;
+use crate::key_spec::{
+    algorithm_to_string, key_type_to_string, parse_hash, parse_sign_hash, KeyTemplate,
 };
-use parsec_client::core::interface::operations::psa_key_attributes::{
-    Attributes, Lifetime, Policy, Type, UsageFlags,
+use crate::subcommands::create_key::create_key_from_template;
+use log::error;
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Algorithm, AsymmetricEncryption, AsymmetricSignature, Hash, SignHash,
 };
+use parsec_client::core::interface::operations::psa_key_attributes::Type;
 use parsec_client::BasicClient;
+use std::str::FromStr;
 use structopt::StructOpt;
 
 /// Create a RSA key pair.
 #[derive(Debug, StructOpt)]
 pub struct CreateRsaKey {
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
 
     /// This command creates RSA encryption keys by default. Supply this flag to create a signing key instead.
     /// Signing keys, by default, will specify the SHA-256 hash algorithm and use PKCS#1 v1.5.
@@ -31,64 +38,102 @@ pub struct CreateRsaKey {
     #[structopt(short = "b", long = "bits")]
     bits: Option<usize>,
 
+    /// Signature scheme of a signing key: pkcs1 (PKCS#1 v1.5, default), pss or raw (PKCS#1 v1.5 over data
+    /// which is not hashed by the service; `sign` encodes the hash itself).
+    #[structopt(long = "scheme", requires = "is-for-signing")]
+    scheme: Option<SignatureScheme>,
+
+    /// Hash algorithm of a signing key: sha256 (default), sha384, sha512... or "any" to let the hash be
+    /// chosen when signing. Raw signing keys do not have a hash algorithm.
+    #[structopt(long = "hash", requires = "is-for-signing")]
+    hash: Option<String>,
+
     /// Specifies if the RSA key should be created with permitted RSA OAEP (SHA256) encryption algorithm
     /// instead of the default RSA PKCS#1 v1.5 one.
     #[structopt(short = "o", long = "oaep")]
     oaep: bool,
+
+    /// Hash algorithm used with RSA OAEP: sha1, sha256 (default), sha384 or sha512. Implies --oaep.
+    #[structopt(long = "oaep-hash", conflicts_with = "is-for-signing")]
+    oaep_hash: Option<String>,
+
+    /// Create a volatile key, which is not stored persistently by the service.
+    #[structopt(long = "volatile")]
+    volatile: bool,
+}
+
+/// Signature scheme of RSA signing keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    /// RSA PKCS#1 v1.5 signature of a hash.
+    Pkcs1,
+    /// RSA PSS signature of a hash.
+    Pss,
+    /// RSA PKCS#1 v1.5 signature of data given as is.
+    Raw,
+}
+
+impl FromStr for SignatureScheme {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "pkcs1" => Ok(SignatureScheme::Pkcs1),
+            "pss" => Ok(SignatureScheme::Pss),
+            "raw" => Ok(SignatureScheme::Raw),
+            _ => Err(format!(
+                "unknown signature scheme \"{}\", expected pkcs1, pss or raw",
+                input
+            )),
+        }
+    }
 }
 
 impl CreateRsaKey {
     /// Exports a key.
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
-        let policy = if self.is_for_signing {
-            info!("Creating RSA signing key...");
-            Policy {
-                usage_flags: {
-                    let mut usage_flags = UsageFlags::default();
-                    let _ = usage_flags
-                        .set_sign_hash()
-                        .set_verify_hash()
-                        .set_sign_message()
-                        .set_verify_message();
-                    usage_flags
-                },
-                permitted_algorithms: AsymmetricSignature::RsaPkcs1v15Sign {
-                    hash_alg: SignHash::Specific(Hash::Sha256),
-                }
-                .into(),
-            }
+        let (usage, algorithm): (&[&str], Algorithm) = if self.is_for_signing {
+            (&["sign", "verify"], self.signature_algorithm()?.into())
         } else {
-            info!("Creating RSA encryption key...");
-            Policy {
-                usage_flags: {
-                    let mut usage_flags = UsageFlags::default();
-                    let _ = usage_flags.set_encrypt().set_decrypt();
-                    usage_flags
+            let algorithm = match &self.oaep_hash {
+                Some(hash) => AsymmetricEncryption::RsaOaep {
+                    hash_alg: parse_hash(hash)?,
                 },
-                permitted_algorithms: if self.oaep {
-                    AsymmetricEncryption::RsaOaep {
-                        hash_alg: Hash::Sha256,
-                    }
-                    .into()
-                } else {
-                    AsymmetricEncryption::RsaPkcs1v15Crypt.into()
+                None if self.oaep => AsymmetricEncryption::RsaOaep {
+                    hash_alg: Hash::Sha256,
                 },
-            }
+                None => AsymmetricEncryption::RsaPkcs1v15Crypt,
+            };
+            (&["encrypt", "decrypt"], algorithm.into())
         };
 
-        let attributes = Attributes {
-            lifetime: Lifetime::Persistent,
-            key_type: Type::RsaKeyPair,
+        let template = KeyTemplate {
+            key_type: Some(key_type_to_string(Type::RsaKeyPair)),
             // No prior validation of 'bits' argument. We have to let the service (and back-end hardware)
             // decide what is valid. The PSA specification does not enforce any minimum/maximum/supported
-            // sizes for RSA keys.
-            bits: self.bits.unwrap_or(2048),
-            policy,
+            // sizes for RSA keys. The default size is 2048 bits.
+            bits: self.bits,
+            lifetime: self.volatile.then(|| String::from("volatile")),
+            usage: usage.iter().map(|flag| flag.to_string()).collect(),
+            algorithm: Some(algorithm_to_string(algorithm)),
         };
 
-        basic_client.psa_generate_key(&self.key_name, attributes)?;
+        create_key_from_template(&basic_client, &self.key_name, &template)
+    }
 
-        info!("Key \"{}\" created.", self.key_name);
-        Ok(())
+    fn signature_algorithm(&self) -> Result<AsymmetricSignature> {
+        let hash_alg = match &self.hash {
+            Some(hash) => parse_sign_hash(hash)?,
+            None => SignHash::Specific(Hash::Sha256),
+        };
+        match self.scheme.unwrap_or(SignatureScheme::Pkcs1) {
+            SignatureScheme::Pkcs1 => Ok(AsymmetricSignature::RsaPkcs1v15Sign { hash_alg }),
+            SignatureScheme::Pss => Ok(AsymmetricSignature::RsaPss { hash_alg }),
+            SignatureScheme::Raw if self.hash.is_some() => {
+                error!("Raw signing keys do not have a hash algorithm");
+                Err(ToolErrorKind::WrongKeyAlgorithm.into())
+            }
+            SignatureScheme::Raw => Ok(AsymmetricSignature::RsaPkcs1v15SignRaw),
+        }
     }
 }
@@ -0,0 +1,175 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Create a symmetric encryption key.
+//!
+//! The key will be a 256-bit AES key by default, used for authenticated encryption with AES-GCM.
+//! HMAC keys can be created instead with `--hmac`. The Parsec client does not expose MAC
+//! operations, so such keys can not be used by the tool itself.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_ref::KeyRef;
+use crate::key_spec::{algorithm_to_string, parse_algorithm, parse_hash};
+use crate::subcommands::create_key::generate_key;
+use log::{error, info, warn};
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, Cipher, FullLengthMac, Hash, Mac,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{
+    Attributes, Lifetime, Policy, Type, UsageFlags,
+};
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Create a symmetric encryption key.
+#[derive(Debug, StructOpt)]
+pub struct CreateSymmetricKey {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Create a ChaCha20 key instead of an AES key.
+    #[structopt(long = "chacha20")]
+    chacha20: bool,
+
+    /// Specifies the size of the key in bits: 128, 192 or 256 (default) for AES keys. ChaCha20 keys are
+    /// always 256 bits.
+    #[structopt(short = "b", long = "bits")]
+    bits: Option<usize>,
+
+    /// Permitted encryption algorithm: gcm (default for AES keys), ccm or chacha20-poly1305 (default for
+    /// ChaCha20 keys). A tag length in bytes can be given for a shortened tag, e.g. gcm(12). The
+    /// unauthenticated cipher modes cbc-pkcs7, cbc-no-padding, ctr, cfb, ofb and ecb-no-padding (AES) or
+    /// stream-cipher (ChaCha20) are also accepted for interoperability with legacy systems.
+    #[structopt(short = "a", long = "algorithm")]
+    algorithm: Option<String>,
+
+    /// Create an HMAC key using this hash algorithm (sha256, sha384 or sha512) instead of an encryption
+    /// key. The size of the key defaults to the output size of the hash.
+    #[structopt(long = "hmac", conflicts_with_all = &["chacha20", "algorithm"])]
+    hmac: Option<String>,
+
+    /// Create a volatile key, which is not stored persistently by the service.
+    #[structopt(long = "volatile")]
+    volatile: bool,
+}
+
+impl CreateSymmetricKey {
+    /// Creates a symmetric key.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        if let Some(hash) = &self.hmac {
+            return self.create_hmac_key(basic_client, parse_hash(hash)?);
+        }
+
+        let (key_type, bits) = if self.chacha20 {
+            match self.bits {
+                None | Some(256) => (Type::Chacha20, 256),
+                Some(bits) => {
+                    error!("ChaCha20 keys are 256 bits long, not {}", bits);
+                    return Err(ToolErrorKind::NotSupported.into());
+                }
+            }
+        } else {
+            match self.bits {
+                None => (Type::Aes, 256),
+                Some(bits @ 128) | Some(bits @ 192) | Some(bits @ 256) => (Type::Aes, bits),
+                Some(bits) => {
+                    error!("AES keys are 128, 192 or 256 bits long, not {}", bits);
+                    return Err(ToolErrorKind::NotSupported.into());
+                }
+            }
+        };
+
+        let permitted_algorithms = match &self.algorithm {
+            Some(algorithm) => parse_algorithm(algorithm)?,
+            None if self.chacha20 => {
+                Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Chacha20Poly1305).into()
+            }
+            None => Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm).into(),
+        };
+        let compatible = match permitted_algorithms {
+            Algorithm::Aead(alg) => alg.is_chacha20_poly1305_alg() == self.chacha20,
+            Algorithm::Cipher(alg) => (alg == Cipher::StreamCipher) == self.chacha20,
+            _ => false,
+        };
+        if let Algorithm::Cipher(alg) = permitted_algorithms {
+            warn!(
+                "{:?} is an unauthenticated cipher mode: prefer an AEAD algorithm when possible.",
+                alg
+            );
+        }
+        if !compatible {
+            error!(
+                "Algorithm {} can not be used with {} keys",
+                algorithm_to_string(permitted_algorithms),
+                key_type
+            );
+            return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+        }
+
+        info!(
+            "Creating {}-bit {} key for {}...",
+            bits,
+            key_type,
+            algorithm_to_string(permitted_algorithms)
+        );
+
+        let attributes = Attributes {
+            lifetime: self.lifetime(),
+            key_type,
+            bits,
+            policy: Policy {
+                usage_flags: {
+                    let mut usage_flags = UsageFlags::default();
+                    let _ = usage_flags.set_encrypt().set_decrypt();
+                    usage_flags
+                },
+                permitted_algorithms,
+            },
+        };
+
+        generate_key(&basic_client, &self.key_name, attributes)
+    }
+
+    fn lifetime(&self) -> Lifetime {
+        if self.volatile {
+            Lifetime::Volatile
+        } else {
+            Lifetime::Persistent
+        }
+    }
+
+    fn create_hmac_key(&self, basic_client: BasicClient, hash_alg: Hash) -> Result<()> {
+        let hash_bits = match hash_alg {
+            Hash::Sha256 => 256,
+            Hash::Sha384 => 384,
+            Hash::Sha512 => 512,
+            _ => {
+                error!("HMAC keys can only use SHA-256, SHA-384 or SHA-512");
+                return Err(ToolErrorKind::NotSupported.into());
+            }
+        };
+        let bits = self.bits.unwrap_or(hash_bits);
+        if bits == 0 || bits % 8 != 0 {
+            error!("HMAC keys must be a whole number of bytes long");
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+
+        info!("Creating {}-bit HMAC-{:?} key...", bits, hash_alg);
+
+        let attributes = Attributes {
+            lifetime: self.lifetime(),
+            key_type: Type::Hmac,
+            bits,
+            policy: Policy {
+                usage_flags: {
+                    let mut usage_flags = UsageFlags::default();
+                    let _ = usage_flags.set_sign_message().set_verify_message();
+                    usage_flags
+                },
+                permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac { hash_alg }).into(),
+            },
+        };
+
+        generate_key(&basic_client, &self.key_name, attributes)
+    }
+}
@@ -3,10 +3,19 @@
 
 //! Decrypts data.
 //!
-//! Will use the algorithm set to the key's policy during creation.
+//! Will use the algorithm set to the key's policy during creation. Data encrypted with a
+//! symmetric key is expected in the container format output by `encrypt`, unless another
+//! `--format` is given for keys with an unauthenticated cipher mode policy.
+//!
+//! If the key name is the alias of a rotated key, all its versions are tried, from the current one to
+//! the oldest. Decrypting with the wrong version of a key with an unauthenticated cipher mode policy
+//! usually returns garbage instead of failing, so the fallback is only reliable for other policies.
 
+use crate::ciphertext::{CipherFormat, Ciphertext};
 use crate::error::{Result, ToolErrorKind};
-use log::{error, info};
+use crate::key_alias::key_versions;
+use crate::key_ref::KeyRef;
+use log::{error, info, warn};
 use parsec_client::core::interface::operations::psa_algorithm::Algorithm;
 use parsec_client::BasicClient;
 use structopt::StructOpt;
@@ -15,7 +24,21 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 pub struct Decrypt {
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
+
+    /// Additional authenticated data (UTF-8 string) given when encrypting, only for keys with an AEAD
+    /// policy.
+    #[structopt(long = "aad")]
+    aad: Option<String>,
+
+    /// Input format of data encrypted with an unauthenticated cipher mode: container,
+    /// iv-prepended (IV followed by the ciphertext) or iv-separate (IV given with --iv).
+    #[structopt(long = "format", default_value = "container")]
+    format: CipherFormat,
+
+    /// IV base64 encoded, for the iv-separate format.
+    #[structopt(long = "iv", required_if("format", "iv-separate"))]
+    iv: Option<String>,
 
     /// Ciphertext base64 encoded
     input_data: String,
@@ -26,22 +49,96 @@ impl Decrypt {
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
         let input = base64::decode(self.input_data.as_bytes())?;
 
+        let versions = key_versions(&basic_client, &self.key_name)?;
+        let mut plaintext = None;
+        for (index, key_name) in versions.iter().enumerate() {
+            match self.decrypt(&basic_client, key_name, &input) {
+                Ok(output) => {
+                    plaintext = Some(output);
+                    break;
+                }
+                Err(e) if index + 1 == versions.len() => return Err(e),
+                Err(e) => info!(
+                    "Could not decrypt with \"{}\" ({}), trying an older version.",
+                    key_name, e
+                ),
+            }
+        }
+
+        let plaintext = String::from_utf8_lossy(&plaintext.unwrap_or_default()).to_string();
+
+        println!("{}", plaintext);
+
+        Ok(())
+    }
+
+    // Decrypts data with one version of the key.
+    fn decrypt(&self, basic_client: &BasicClient, key_name: &str, input: &[u8]) -> Result<Vec<u8>> {
         let alg = basic_client
-            .key_attributes(&self.key_name)?
+            .key_attributes(key_name)?
             .policy
             .permitted_algorithms;
 
         let plaintext = match alg {
+            Algorithm::AsymmetricEncryption(_) | Algorithm::Cipher(_) if self.aad.is_some() => {
+                error!("Additional data can only be authenticated by keys with an AEAD policy.");
+                return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+            }
+            Algorithm::AsymmetricEncryption(_) | Algorithm::Aead(_)
+                if self.format != CipherFormat::Container =>
+            {
+                error!("The input format can only be chosen for keys with a cipher mode policy.");
+                return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+            }
             Algorithm::AsymmetricEncryption(alg) => {
                 info!("Decrypting data with {:?}...", alg);
-                basic_client.psa_asymmetric_decrypt(&self.key_name, alg, &input, None)?
+                basic_client.psa_asymmetric_decrypt(key_name, alg, input, None)?
             }
-            Algorithm::Cipher(_) | Algorithm::Aead(_) => {
-                error!(
-                    "Key's algorithm is {:?} which is not currently supported for decryption.",
+            Algorithm::Aead(alg) => {
+                let ciphertext = Ciphertext::from_bytes(input)?;
+                if ciphertext.algorithm != Algorithm::Aead(alg) {
+                    error!(
+                        "The data was encrypted with {:?} but the key's algorithm is {:?}.",
+                        ciphertext.algorithm, alg
+                    );
+                    return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                }
+                info!("Decrypting data with {:?}...", alg);
+                let aad = self.aad.as_deref().unwrap_or_default().as_bytes();
+                basic_client.psa_aead_decrypt(
+                    key_name,
+                    alg,
+                    &ciphertext.nonce,
+                    aad,
+                    &ciphertext.aead_input(),
+                )?
+            }
+            Algorithm::Cipher(alg) => {
+                warn!(
+                    "{:?} is an unauthenticated cipher mode: modifications of the ciphertext will not be detected.",
                     alg
                 );
-                return Err(ToolErrorKind::NotSupported.into());
+                let input = match self.format {
+                    CipherFormat::Container => {
+                        let ciphertext = Ciphertext::from_bytes(input)?;
+                        if ciphertext.algorithm != Algorithm::Cipher(alg) {
+                            error!(
+                                "The data was encrypted with {:?} but the key's algorithm is {:?}.",
+                                ciphertext.algorithm, alg
+                            );
+                            return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                        }
+                        ciphertext.cipher_input()
+                    }
+                    CipherFormat::IvPrepended => input.to_vec(),
+                    CipherFormat::IvSeparate => {
+                        let mut iv = base64::decode(self.iv.as_deref().unwrap_or_default())?;
+                        iv.extend_from_slice(input);
+                        iv
+                    }
+                };
+                info!("Decrypting data with {:?}...", alg);
+                basic_client.psa_cipher_decrypt(key_name.to_string(), alg, &input)?
             }
             other => {
                 error!(
@@ -52,10 +149,6 @@ impl Decrypt {
             }
         };
 
-        let plaintext = String::from_utf8_lossy(&plaintext).to_string();
-
-        println!("{}", plaintext);
-
-        Ok(())
+        Ok(plaintext)
     }
 }
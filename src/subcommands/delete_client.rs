@@ -4,6 +4,8 @@
 //! Delete all data a client has in the service (admin operation).
 
 use crate::error::Result;
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::util::confirm;
 
 use log::info;
 use parsec_client::BasicClient;
@@ -14,10 +16,22 @@ use structopt::StructOpt;
 pub struct DeleteClient {
     #[structopt(short = "c", long = "client")]
     client: String,
+
+    /// Do not ask for confirmation.
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
 }
 
 impl DeleteClient {
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        GuardrailPolicy::load()?.check_delete_client(self.yes)?;
+        if !self.yes {
+            confirm(&format!(
+                "Delete all the keys of client \"{}\"?",
+                self.client
+            ))?;
+        }
+
         basic_client.delete_client(&self.client)?;
 
         info!("Client \"{}\" deleted.", self.client);
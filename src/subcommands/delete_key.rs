@@ -4,6 +4,9 @@
 //! Delete a key.
 
 use crate::error::Result;
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_metadata::remove_key_metadata;
+use crate::key_ref::KeyRef;
 use log::info;
 use parsec_client::BasicClient;
 use structopt::StructOpt;
@@ -12,15 +15,21 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 pub struct DeleteKey {
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
+
+    /// Confirm the deletion, when the guardrail policy requires it.
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
 }
 
 impl DeleteKey {
     /// Destroys a key.
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        GuardrailPolicy::load()?.check_deletion(&self.key_name, self.yes)?;
         info!("Deleting a key...");
 
         basic_client.psa_destroy_key(&self.key_name)?;
+        remove_key_metadata(&self.key_name, basic_client.implicit_provider())?;
 
         info!("Key \"{}\" deleted.", self.key_name);
         Ok(())
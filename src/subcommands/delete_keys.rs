@@ -0,0 +1,119 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Delete all the keys matching name patterns and filters.
+//!
+//! The keys to delete are printed first and confirmation is asked, unless `--yes` is given. A key which
+//! can not be deleted does not stop the deletion of the others.
+
+use crate::error::Result;
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_alias::AliasFile;
+use crate::key_filter::{name_pattern, KeyFilter};
+use crate::key_metadata::MetadataStore;
+use crate::util::confirm;
+use log::{error, info};
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Delete all the keys matching name patterns and filters.
+#[derive(Debug, StructOpt)]
+pub struct DeleteKeys {
+    /// Glob patterns (or regular expressions with --regex) that the key names have to match, e.g.
+    /// "anta-key-*". A key is selected if it matches any of them.
+    #[structopt(required = true)]
+    patterns: Vec<String>,
+
+    /// Interpret the patterns as regular expressions.
+    #[structopt(long = "regex")]
+    regex: bool,
+
+    #[structopt(flatten)]
+    filter: KeyFilter,
+
+    /// Only print the keys which would be deleted.
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+
+    /// Do not ask for confirmation.
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+}
+
+impl DeleteKeys {
+    /// Destroys the selected keys.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let patterns = self
+            .patterns
+            .iter()
+            .map(|pattern| name_pattern(pattern, self.regex))
+            .collect::<Result<Vec<_>>>()?;
+        let keys: Vec<_> = self
+            .filter
+            .select(basic_client.list_keys()?)?
+            .into_iter()
+            .filter(|key| patterns.iter().any(|pattern| pattern.is_match(&key.name)))
+            .collect();
+
+        if keys.is_empty() {
+            info!("No keys match.");
+            return Ok(());
+        }
+        info!("Keys to delete:");
+        for key in &keys {
+            println!("* {} ({})", key.name, key.provider_id);
+        }
+        if self.dry_run {
+            info!("Dry run, no key deleted.");
+            return Ok(());
+        }
+        let policy = GuardrailPolicy::load()?;
+        for key in &keys {
+            policy.check_provider(key.provider_id)?;
+            policy.check_deletion(&key.name, self.yes)?;
+        }
+        if !self.yes {
+            confirm(&format!("Delete {} keys?", keys.len()))?;
+        }
+
+        let mut metadata = MetadataStore::load()?;
+        let mut metadata_changed = false;
+        let mut aliases = AliasFile::load()?;
+        let mut aliases_changed = false;
+        let mut first_error = None;
+        let mut failures = 0;
+        for key in &keys {
+            basic_client.set_implicit_provider(key.provider_id);
+            match basic_client.psa_destroy_key(&key.name) {
+                Ok(()) => {
+                    metadata_changed |= metadata.remove(&key.name, key.provider_id as u8);
+                    aliases_changed |= aliases.remove_key(&key.name, key.provider_id as u8);
+                    info!("Key \"{}\" deleted.", key.name);
+                }
+                Err(e) => {
+                    error!("Key \"{}\" could not be deleted: {}", key.name, e);
+                    failures += 1;
+                    let _ = first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        if metadata_changed {
+            metadata.save()?;
+        }
+        if aliases_changed {
+            aliases.save()?;
+        }
+
+        match first_error {
+            Some(e) => {
+                error!("{} of {} keys could not be deleted", failures, keys.len());
+                Err(e.into())
+            }
+            None => {
+                info!("{} keys deleted.", keys.len());
+                Ok(())
+            }
+        }
+    }
+}
@@ -3,20 +3,29 @@
 
 //! Encrypts some plaintext data with a specified key.
 //!
-//! Will use the algorithm set to the key's policy during creation. Currently only
-//! supports asymmetric encryption such as RSA, in which case the specified key must
-//! be a public key or an asymmetric key pair (of which the public part will be
-//! used). It is not possible to encrypt data using the private part of an asymmetric
-//! key pair. Encryption with symmetric keys will be added in the future.
+//! Will use the algorithm set to the key's policy during creation. For asymmetric
+//! encryption such as RSA, the specified key must be a public key or an asymmetric key
+//! pair (of which the public part will be used). It is not possible to encrypt data
+//! using the private part of an asymmetric key pair. No salt is used.
 //!
-//! No salt is used.
+//! For symmetric keys with an AEAD policy, a random nonce is generated by the service and
+//! the output is a container holding the algorithm, nonce, tag and ciphertext (see the
+//! `ciphertext` module). Additional authenticated data can be given with `--aad`.
+//!
+//! Keys with an unauthenticated cipher mode policy (CBC, CTR, ECB...) are supported for
+//! interoperability with legacy systems, with a warning. The IV is generated by the
+//! service; `--format` selects whether it is output in a container, prepended to the
+//! ciphertext or printed on its own line before the ciphertext.
 //!
 //! The input is a plain text message string, which is treated as raw bytes.
 //!
 //! The output is base64-encoded ciphertext.
 
+use crate::ciphertext::{CipherFormat, Ciphertext, AEAD_NONCE_LENGTH};
 use crate::error::{Result, ToolErrorKind};
-use log::{error, info};
+use crate::key_alias::resolve_key_name;
+use crate::key_ref::KeyRef;
+use log::{error, info, warn};
 use parsec_client::core::interface::operations::psa_algorithm::Algorithm;
 use parsec_client::BasicClient;
 use structopt::StructOpt;
@@ -25,7 +34,18 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 pub struct Encrypt {
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
+
+    /// Additional authenticated data (UTF-8 string), only for keys with an AEAD policy. The same data
+    /// must be given to decrypt.
+    #[structopt(long = "aad")]
+    aad: Option<String>,
+
+    /// Output format of data encrypted with an unauthenticated cipher mode: container,
+    /// iv-prepended (IV followed by the ciphertext) or iv-separate (IV and ciphertext in base64 on two
+    /// lines).
+    #[structopt(long = "format", default_value = "container")]
+    format: CipherFormat,
 
     /// Plaintext input string.
     input_data: String,
@@ -36,22 +56,52 @@ impl Encrypt {
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
         let input = self.input_data.as_bytes();
 
-        let alg = basic_client
-            .key_attributes(&self.key_name)?
-            .policy
-            .permitted_algorithms;
+        let key_name = resolve_key_name(&basic_client, &self.key_name)?;
+        let attributes = basic_client.key_attributes(&key_name)?;
+        let alg = attributes.policy.permitted_algorithms;
 
         let ciphertext = match alg {
+            Algorithm::AsymmetricEncryption(_) | Algorithm::Cipher(_) if self.aad.is_some() => {
+                error!("Additional data can only be authenticated by keys with an AEAD policy.");
+                return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+            }
+            Algorithm::AsymmetricEncryption(_) | Algorithm::Aead(_)
+                if self.format != CipherFormat::Container =>
+            {
+                error!("The output format can only be chosen for keys with a cipher mode policy.");
+                return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+            }
             Algorithm::AsymmetricEncryption(alg) => {
                 info!("Encrypting data with {:?}...", alg);
-                basic_client.psa_asymmetric_encrypt(&self.key_name, alg, input, None)?
+                basic_client.psa_asymmetric_encrypt(&key_name, alg, input, None)?
             }
-            Algorithm::Cipher(_) | Algorithm::Aead(_) => {
-                error!(
-                    "Key's algorithm is {:?} which is not currently supported for encryption.",
+            Algorithm::Aead(alg) => {
+                info!("Encrypting data with {:?}...", alg);
+                let nonce = basic_client.psa_generate_random(AEAD_NONCE_LENGTH)?;
+                let aad = self.aad.as_deref().unwrap_or_default().as_bytes();
+                let output = basic_client.psa_aead_encrypt(&key_name, alg, &nonce, aad, input)?;
+                Ciphertext::from_aead_output(alg, nonce, output)?.to_bytes()?
+            }
+            Algorithm::Cipher(alg) => {
+                warn!(
+                    "{:?} is an unauthenticated cipher mode: modifications of the ciphertext will not be detected.",
                     alg
                 );
-                return Err(ToolErrorKind::NotSupported.into());
+                info!("Encrypting data with {:?}...", alg);
+                let output = basic_client.psa_cipher_encrypt(key_name.clone(), alg, input)?;
+                match self.format {
+                    CipherFormat::Container => {
+                        Ciphertext::from_cipher_output(alg, attributes.key_type, output)?
+                            .to_bytes()?
+                    }
+                    CipherFormat::IvPrepended => output,
+                    CipherFormat::IvSeparate => {
+                        let ciphertext =
+                            Ciphertext::from_cipher_output(alg, attributes.key_type, output)?;
+                        println!("{}", base64::encode(&ciphertext.nonce));
+                        ciphertext.ciphertext
+                    }
+                }
             }
             other => {
                 error!(
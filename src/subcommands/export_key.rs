@@ -0,0 +1,137 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exports the private key material of a key pair.
+//!
+//! Only keys whose policy allows exporting can be exported. The key is written in PEM format, as a
+//! PKCS#8 `PrivateKeyInfo` by default.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_format::{encode_pkcs8, encode_sec1, encrypt_pkcs8};
+use crate::key_ref::KeyRef;
+use crate::util::read_password;
+use log::{error, info};
+use parsec_client::core::interface::operations::psa_key_attributes::Type;
+use parsec_client::BasicClient;
+use std::convert::TryInto;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Exports a PEM-encoded private key.
+#[derive(Debug, StructOpt)]
+pub struct ExportKey {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Export RSA private key in PKCS#1 format.
+    #[structopt(long = "pkcs1", conflicts_with = "sec1")]
+    pkcs1: bool,
+
+    /// Export ECC private key in SEC1 format.
+    #[structopt(long = "sec1")]
+    sec1: bool,
+
+    /// Encrypt the PKCS#8 private key with this password (PBES2 with PBKDF2-SHA256 and AES-256-CBC).
+    /// On the command line, the password is visible to the other users of the system and is
+    /// kept in the shell history: prefer --password-file.
+    #[structopt(long = "password", conflicts_with_all = &["pkcs1", "sec1"])]
+    password: Option<String>,
+
+    /// Encrypt the PKCS#8 private key with the password on the first line of this file.
+    #[structopt(
+        long = "password-file",
+        parse(from_os_str),
+        conflicts_with_all = &["password", "pkcs1", "sec1"]
+    )]
+    password_file: Option<PathBuf>,
+}
+
+impl ExportKey {
+    /// Exports a private key.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let psa_key_attributes = basic_client.key_attributes(&self.key_name)?;
+
+        if !psa_key_attributes.is_exportable() {
+            error!(
+                "Key \"{}\" was not created with the export usage flag and cannot be exported",
+                self.key_name
+            );
+            return Err(ToolErrorKind::NotExportable.into());
+        }
+
+        let key_type = psa_key_attributes.key_type;
+        let bits = psa_key_attributes.bits;
+
+        let (tag, contents) = match key_type {
+            Type::RsaKeyPair => {
+                if self.sec1 {
+                    error!("SEC1 format doesn't support RSA keys");
+                    return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                }
+                let private_key = basic_client.psa_export_key(&self.key_name)?;
+                if self.pkcs1 {
+                    ("RSA PRIVATE KEY", private_key)
+                } else {
+                    (
+                        "PRIVATE KEY",
+                        encode_pkcs8(key_type, bits, &private_key, None)?,
+                    )
+                }
+            }
+            Type::EccKeyPair { .. } => {
+                if self.pkcs1 {
+                    error!("PKCS1 format doesn't support ECC keys");
+                    return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                }
+                let private_key = basic_client.psa_export_key(&self.key_name)?;
+                let public_key = basic_client.psa_export_public_key(&self.key_name)?;
+                if self.sec1 {
+                    (
+                        "EC PRIVATE KEY",
+                        encode_sec1(key_type, bits, &private_key, Some(&public_key))?,
+                    )
+                } else {
+                    (
+                        "PRIVATE KEY",
+                        encode_pkcs8(key_type, bits, &private_key, Some(&public_key))?,
+                    )
+                }
+            }
+            _ => {
+                error!("Unsupported type of key");
+                return Err(ToolErrorKind::NotSupported.into());
+            }
+        };
+
+        let password = read_password(self.password.as_deref(), self.password_file.as_deref())?;
+        let (tag, contents) = match password {
+            Some(password) => {
+                info!("Encrypting the private key...");
+                let salt = basic_client.psa_generate_random(16)?;
+                let iv = basic_client.psa_generate_random(16)?;
+                let iv = iv.as_slice().try_into().map_err(|_| {
+                    error!("The service returned an IV of the wrong size");
+                    ToolErrorKind::IncorrectData
+                })?;
+                (
+                    "ENCRYPTED PRIVATE KEY",
+                    encrypt_pkcs8(&contents, &password, &salt, iv)?,
+                )
+            }
+            None => (tag, contents),
+        };
+
+        let pem_encoded = pem::encode_config(
+            &pem::Pem {
+                tag: String::from(tag),
+                contents,
+            },
+            pem::EncodeConfig {
+                line_ending: pem::LineEnding::LF,
+            },
+        );
+
+        print!("{}", pem_encoded);
+        Ok(())
+    }
+}
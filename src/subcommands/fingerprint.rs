@@ -0,0 +1,84 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prints the fingerprints of a public key, and DNS records containing them.
+//!
+//! The SHA-256 of the SubjectPublicKeyInfo, the JWK thumbprint and the OpenSSH fingerprint are printed,
+//! together with a DANE TLSA (3 1 1) record and an SSHFP record ready to be added to a DNS zone.
+//! Fingerprints which do not exist for the type of the key are omitted, or make the command fail if
+//! it was asked for with `--format`.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::fingerprint::{FingerprintKind, Fingerprints};
+use crate::key_alias::resolve_key_name;
+use crate::key_ref::KeyRef;
+use crate::key_spec::key_type_to_string;
+use log::error;
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Prints the fingerprints of a public key.
+#[derive(Debug, StructOpt)]
+pub struct Fingerprint {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Only print one fingerprint: spki-sha256, jwk, ssh, tlsa or sshfp.
+    #[structopt(long = "format")]
+    format: Option<FingerprintKind>,
+
+    /// Host name used in the DNS records. Without host, the records are relative to the zone origin.
+    #[structopt(long = "host")]
+    host: Option<String>,
+
+    /// TCP port of the TLS service, used in the TLSA record.
+    #[structopt(long = "port", default_value = "443")]
+    port: u16,
+}
+
+impl Fingerprint {
+    /// Prints the fingerprints.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let key_name = resolve_key_name(&basic_client, &self.key_name)?;
+        let public_key = basic_client.psa_export_public_key(&key_name)?;
+        let attributes = basic_client.key_attributes(&key_name)?;
+        let fingerprints = Fingerprints::new(attributes.key_type, attributes.bits, &public_key)?;
+        let host = self.host.as_deref();
+        let tlsa = fingerprints.tlsa_record(host, self.port);
+        let sshfp = fingerprints.sshfp_record(host);
+
+        match self.format {
+            Some(FingerprintKind::Tlsa) => println!("{}", tlsa),
+            Some(kind) => {
+                let fingerprint = match kind {
+                    FingerprintKind::Sshfp => sshfp,
+                    _ => fingerprints.get(kind),
+                };
+                match fingerprint {
+                    Some(fingerprint) => println!("{}", fingerprint),
+                    None => {
+                        error!(
+                            "Keys of type {} have no fingerprint of this format",
+                            key_type_to_string(attributes.key_type)
+                        );
+                        return Err(ToolErrorKind::NotSupported.into());
+                    }
+                }
+            }
+            None => {
+                println!("SPKI SHA-256:   {}", fingerprints.spki_sha256);
+                if let Some(jwk_thumbprint) = &fingerprints.jwk_thumbprint {
+                    println!("JWK thumbprint: {}", jwk_thumbprint);
+                }
+                if let Some(ssh) = fingerprints.ssh() {
+                    println!("OpenSSH:        {}", ssh);
+                }
+                println!("TLSA:           {}", tlsa);
+                if let Some(sshfp) = sshfp {
+                    println!("SSHFP:          {}", sshfp);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Imports a key.
+//!
+//! The key is read from a PEM or DER encoded file and converted to the format expected by the Parsec
+//! service. Its policy follows the defaults of the key creation commands: RSA keys are encryption keys
+//! unless `--for-signing` is given, ECC keys are ECDSA signing keys using the hash matching the curve
+//! size, and Montgomery curve (X25519, X448) keys are ECDH key agreement keys. Public keys are only given the public part of that policy (encrypt or verify).
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_format::decode_key;
+use crate::key_ref::KeyRef;
+use crate::key_spec::default_ecdsa_hash;
+use crate::subcommands::create_key::warn_if_volatile;
+use crate::util::read_password;
+use log::{error, info};
+use parsec_client::core::interface::operations::psa_algorithm::{
+    AsymmetricEncryption, AsymmetricSignature, Hash, KeyAgreement, RawKeyAgreement, SignHash,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{
+    Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags,
+};
+use parsec_client::BasicClient;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Imports a key.
+#[derive(Debug, StructOpt)]
+pub struct ImportKey {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Path of the file containing the key. PKCS#1 RSA keys, PKCS#8 private keys (optionally encrypted),
+    /// SEC1 EC private keys and SubjectPublicKeyInfo public keys are accepted, in PEM or DER format.
+    #[structopt(short = "i", long = "input-file", parse(from_os_str))]
+    input_file: PathBuf,
+
+    /// Password used to decrypt an encrypted PKCS#8 private key.
+    /// On the command line, the password is visible to the other users of the system and is
+    /// kept in the shell history: prefer --password-file.
+    #[structopt(long = "password")]
+    password: Option<String>,
+
+    /// Path of a file whose first line is the password of an encrypted PKCS#8 private key.
+    #[structopt(
+        long = "password-file",
+        parse(from_os_str),
+        conflicts_with = "password"
+    )]
+    password_file: Option<PathBuf>,
+
+    /// RSA keys are imported as encryption keys by default. Supply this flag to import a signing key instead.
+    /// Signing keys will specify the SHA-256 hash algorithm and use PKCS#1 v1.5.
+    #[structopt(short = "s", long = "for-signing")]
+    is_for_signing: bool,
+
+    /// Specifies if the RSA key should be imported with permitted RSA OAEP (SHA256) encryption algorithm
+    /// instead of the default RSA PKCS#1 v1.5 one.
+    #[structopt(short = "o", long = "oaep")]
+    oaep: bool,
+
+    /// Import the key as a volatile key, which is not stored persistently by the service.
+    #[structopt(long = "volatile")]
+    volatile: bool,
+}
+
+impl ImportKey {
+    /// Imports a key.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let input = std::fs::read(&self.input_file)?;
+        let password = read_password(self.password.as_deref(), self.password_file.as_deref())?;
+        let key = decode_key(&input, password.as_deref())?;
+
+        info!("Importing {} ({} bits)...", key.key_type, key.bits);
+
+        let lifetime = if self.volatile {
+            Lifetime::Volatile
+        } else {
+            Lifetime::Persistent
+        };
+        warn_if_volatile(&self.key_name, lifetime);
+
+        let attributes = Attributes {
+            lifetime,
+            key_type: key.key_type,
+            bits: key.bits,
+            policy: import_policy(key.key_type, key.bits, self.is_for_signing, self.oaep)?,
+        };
+
+        basic_client.psa_import_key(&self.key_name, &key.data, attributes)?;
+
+        info!("Key \"{}\" imported.", self.key_name);
+        Ok(())
+    }
+}
+
+/// Returns the policy given to imported keys: ECDSA for ECC keys, ECDH for Montgomery curve keys, and RSA PKCS#1 v1.5 encryption, RSA OAEP
+/// (SHA-256) encryption or RSA PKCS#1 v1.5 signature (SHA-256) for RSA keys. Public keys only get the public
+/// part of the policy.
+pub fn import_policy(key_type: Type, bits: usize, for_signing: bool, oaep: bool) -> Result<Policy> {
+    let mut usage_flags = UsageFlags::default();
+
+    let permitted_algorithms = match key_type {
+        Type::RsaKeyPair | Type::RsaPublicKey if for_signing => {
+            let _ = usage_flags.set_verify_hash().set_verify_message();
+            if key_type == Type::RsaKeyPair {
+                let _ = usage_flags.set_sign_hash().set_sign_message();
+            }
+            AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: SignHash::Specific(Hash::Sha256),
+            }
+            .into()
+        }
+        Type::RsaKeyPair | Type::RsaPublicKey => {
+            let _ = usage_flags.set_encrypt();
+            if key_type == Type::RsaKeyPair {
+                let _ = usage_flags.set_decrypt();
+            }
+            if oaep {
+                AsymmetricEncryption::RsaOaep {
+                    hash_alg: Hash::Sha256,
+                }
+                .into()
+            } else {
+                AsymmetricEncryption::RsaPkcs1v15Crypt.into()
+            }
+        }
+        Type::EccKeyPair {
+            curve_family: EccFamily::Montgomery,
+        }
+        | Type::EccPublicKey {
+            curve_family: EccFamily::Montgomery,
+        } => {
+            // Montgomery curve keys can not sign, they are only used for key agreement.
+            let _ = usage_flags.set_derive();
+            KeyAgreement::Raw(RawKeyAgreement::Ecdh).into()
+        }
+        Type::EccKeyPair { .. } | Type::EccPublicKey { .. } => {
+            let _ = usage_flags.set_verify_hash().set_verify_message();
+            if key_type.is_ecc_key_pair() {
+                let _ = usage_flags.set_sign_hash().set_sign_message();
+            }
+            AsymmetricSignature::Ecdsa {
+                hash_alg: default_ecdsa_hash(bits).into(),
+            }
+            .into()
+        }
+        _ => {
+            error!("Unsupported type of key");
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+
+    Ok(Policy {
+        usage_flags,
+        permitted_algorithms,
+    })
+}
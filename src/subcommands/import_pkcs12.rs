@@ -0,0 +1,227 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Imports the private key of a PKCS#12 file, and stores its certificates.
+//!
+//! The file is decrypted with its password, which also checks its integrity. The private key is
+//! imported with the same policy as with `import-key`. The certificate of the key is stored as
+//! `<key name>.pem` in the certificate directory, by default the `certificates` directory under the
+//! configuration directory of parsec-tool, and the rest of its chain, if any, as
+//! `<key name>.chain.pem`.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_format::decode_key;
+use crate::key_ref::KeyRef;
+use crate::subcommands::create_key::warn_if_volatile;
+use crate::subcommands::import_key::import_policy;
+use crate::util::{config_dir, read_password, temp_path};
+use log::{error, info, warn};
+use p12_keystore::{Certificate, KeyStore};
+use parsec_client::core::interface::operations::psa_key_attributes::{Attributes, Lifetime};
+use parsec_client::BasicClient;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+const CERTIFICATE_DIR_NAME: &str = "certificates";
+
+/// Imports the private key of a PKCS#12 file, and stores its certificates.
+#[derive(Debug, StructOpt)]
+pub struct ImportPkcs12 {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Path of the PKCS#12 file (.p12 or .pfx).
+    #[structopt(short = "i", long = "input-file", parse(from_os_str))]
+    input_file: PathBuf,
+
+    /// Password of the PKCS#12 file. The empty password is used if neither this nor --password-file is
+    /// given. On the command line, the password is visible to the other users of the system and is
+    /// kept in the shell history: prefer --password-file.
+    #[structopt(long = "password")]
+    password: Option<String>,
+
+    /// Path of a file whose first line is the password of the PKCS#12 file.
+    #[structopt(
+        long = "password-file",
+        parse(from_os_str),
+        conflicts_with = "password"
+    )]
+    password_file: Option<PathBuf>,
+
+    /// RSA keys are imported as encryption keys by default. Supply this flag to import a signing key instead.
+    /// Signing keys will specify the SHA-256 hash algorithm and use PKCS#1 v1.5.
+    #[structopt(short = "s", long = "for-signing")]
+    is_for_signing: bool,
+
+    /// Specifies if the RSA key should be imported with permitted RSA OAEP (SHA256) encryption algorithm
+    /// instead of the default RSA PKCS#1 v1.5 one.
+    #[structopt(short = "o", long = "oaep")]
+    oaep: bool,
+
+    /// Import the key as a volatile key, which is not stored persistently by the service.
+    #[structopt(long = "volatile")]
+    volatile: bool,
+
+    /// Directory in which the certificates are stored, instead of the one under the configuration
+    /// directory of parsec-tool.
+    #[structopt(long = "cert-dir", parse(from_os_str))]
+    cert_dir: Option<PathBuf>,
+}
+
+impl ImportPkcs12 {
+    /// Imports the key and stores the certificates.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let cert_dir = self.cert_dir()?;
+        if self.key_name.is_empty() || self.key_name.contains('/') || self.key_name.starts_with('.')
+        {
+            error!(
+                "The key name \"{}\" can not be used as the name of its certificate file",
+                self.key_name
+            );
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+
+        let input = std::fs::read(&self.input_file)?;
+        let password = read_password(self.password.as_deref(), self.password_file.as_deref())?;
+        let keystore =
+            KeyStore::from_pkcs12(&input, password.as_deref().unwrap_or("")).map_err(|e| {
+                error!("Could not read the PKCS#12 file: {}", e);
+                ToolErrorKind::IncorrectData
+            })?;
+        let (_, key_chain) = keystore.private_key_chain().ok_or_else(|| {
+            error!("The PKCS#12 file does not contain a private key with its certificate");
+            ToolErrorKind::NoInput
+        })?;
+        let key = decode_key(key_chain.key(), None)?;
+        std::fs::create_dir_all(&cert_dir)?;
+
+        info!("Importing {} ({} bits)...", key.key_type, key.bits);
+        let lifetime = if self.volatile {
+            Lifetime::Volatile
+        } else {
+            Lifetime::Persistent
+        };
+        warn_if_volatile(&self.key_name, lifetime);
+        let attributes = Attributes {
+            lifetime,
+            key_type: key.key_type,
+            bits: key.bits,
+            policy: import_policy(key.key_type, key.bits, self.is_for_signing, self.oaep)?,
+        };
+
+        // The certificates are written to temporary files before the key is imported, and moved in
+        // place once it is, so that no certificates are stored without their key and the reverse.
+        let (leaf, chain) = key_chain.chain().split_at(1);
+        let leaf_path = cert_dir.join(format!("{}.pem", &*self.key_name));
+        let chain_path = cert_dir.join(format!("{}.chain.pem", &*self.key_name));
+        let mut files = vec![(leaf_path.as_path(), leaf)];
+        if !chain.is_empty() {
+            files.push((chain_path.as_path(), chain));
+        }
+        stage_certificates(&files)?;
+
+        if let Err(e) = basic_client.psa_import_key(&self.key_name, &key.data, attributes) {
+            remove_staged_certificates(&files);
+            return Err(e.into());
+        }
+        info!("Key \"{}\" imported.", self.key_name);
+
+        if let Err(e) = store_staged_certificates(&files) {
+            remove_staged_certificates(&files);
+            error!(
+                "Could not store the certificates, destroying the key \"{}\"",
+                self.key_name
+            );
+            if let Err(destroy_error) = basic_client.psa_destroy_key(&self.key_name) {
+                error!(
+                    "Could not destroy the key \"{}\" in {}, which is left without its \
+                     certificates: {}",
+                    self.key_name,
+                    basic_client.implicit_provider(),
+                    destroy_error
+                );
+            }
+            return Err(e);
+        }
+        info!("Certificate stored in \"{}\".", leaf_path.display());
+        if chain.is_empty() {
+            if chain_path.exists() {
+                std::fs::remove_file(&chain_path)?;
+            }
+        } else {
+            info!(
+                "{} chain certificate(s) stored in \"{}\".",
+                chain.len(),
+                chain_path.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn cert_dir(&self) -> Result<PathBuf> {
+        match (&self.cert_dir, config_dir()) {
+            (Some(dir), _) => Ok(dir.clone()),
+            (None, Some(dir)) => Ok(dir.join(CERTIFICATE_DIR_NAME)),
+            (None, None) => {
+                error!("Neither XDG_CONFIG_HOME nor HOME is set, use --cert-dir");
+                Err(ToolErrorKind::NoInput.into())
+            }
+        }
+    }
+}
+
+// Writes certificates to the temporary files of their PEM files, removing them all if one of them
+// can not be written.
+fn stage_certificates(files: &[(&Path, &[Certificate])]) -> Result<()> {
+    for (path, certificates) in files {
+        if let Err(e) = write_certificates(&temp_path(path), certificates) {
+            error!("Could not write \"{}\": {}", path.display(), e);
+            remove_staged_certificates(files);
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+// Moves the temporary files written by `stage_certificates` in place, replacing the certificates
+// stored before.
+fn store_staged_certificates(files: &[(&Path, &[Certificate])]) -> Result<()> {
+    for (path, _) in files {
+        if path.exists() {
+            warn!("Replacing \"{}\"", path.display());
+        }
+        std::fs::rename(temp_path(path), path).map_err(|e| {
+            error!("Could not write \"{}\": {}", path.display(), e);
+            e
+        })?;
+    }
+    Ok(())
+}
+
+fn remove_staged_certificates(files: &[(&Path, &[Certificate])]) {
+    for (path, _) in files {
+        let _ = std::fs::remove_file(temp_path(path));
+    }
+}
+
+// Writes certificates to a PEM file.
+fn write_certificates(path: &Path, certificates: &[Certificate]) -> std::io::Result<()> {
+    let contents: String = certificates
+        .iter()
+        .map(|certificate| {
+            pem::encode_config(
+                &pem::Pem {
+                    tag: String::from("CERTIFICATE"),
+                    contents: certificate.as_der().to_vec(),
+                },
+                pem::EncodeConfig {
+                    line_ending: pem::LineEnding::LF,
+                },
+            )
+        })
+        .collect();
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()
+}
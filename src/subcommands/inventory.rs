@@ -0,0 +1,225 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reports the keys of all providers, with the findings of an audit policy.
+//!
+//! The report has one entry per key with its provider, name, type, size, permitted algorithm, usage
+//! flags, SHA-256 fingerprint of the public key (for asymmetric keys), exportability and findings. It is
+//! written as CSV, with one line per key, or as a JSON array. The command fails, with exit code 3, if
+//! some findings are at or above the severity given with `--fail-on`.
+
+use crate::audit_policy::{AuditPolicy, Severity};
+use crate::error::{Result, ToolErrorKind};
+use crate::fingerprint::{is_asymmetric, spki_sha256};
+use crate::key_spec::{algorithm_to_string, key_type_to_string, usage_flags_to_strings};
+use log::{error, info, warn};
+use parsec_client::BasicClient;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// Reports the keys of all providers, with the findings of an audit policy.
+#[derive(Debug, StructOpt)]
+pub struct Inventory {
+    /// Format of the report: csv or json.
+    #[structopt(long = "format", default_value = "csv")]
+    format: ReportFormat,
+
+    /// File to write the report to, instead of the standard output.
+    #[structopt(short = "o", long = "output-file", parse(from_os_str))]
+    output_file: Option<PathBuf>,
+
+    /// TOML audit policy. A default policy is used if not given.
+    #[structopt(long = "policy", parse(from_os_str))]
+    policy: Option<PathBuf>,
+
+    /// Fail if some findings have this severity or a higher one: low, medium, high or critical.
+    #[structopt(long = "fail-on")]
+    fail_on: Option<Severity>,
+}
+
+/// Format of the inventory report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Comma-separated values, with a header line.
+    Csv,
+    /// JSON array.
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "unknown report format \"{}\", expected csv or json",
+                input
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct KeyReport {
+    provider_id: u8,
+    provider: String,
+    name: String,
+    #[serde(rename = "type")]
+    key_type: String,
+    bits: usize,
+    algorithm: String,
+    usage: Vec<&'static str>,
+    spki_sha256: Option<String>,
+    exportable: bool,
+    findings: Vec<Finding>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Finding {
+    rule: String,
+    severity: Severity,
+    description: String,
+}
+
+impl Inventory {
+    /// Reports the keys.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let policy = match &self.policy {
+            Some(path) => AuditPolicy::from_file(path)?,
+            None => AuditPolicy::default(),
+        };
+        let providers = basic_client.list_providers()?;
+        let keys = basic_client.list_keys()?;
+
+        let mut reports = Vec::new();
+        for provider in &providers {
+            basic_client.set_implicit_provider(provider.id);
+            for key in keys.iter().filter(|key| key.provider_id == provider.id) {
+                let attributes = key.attributes;
+                let spki_sha256 = if is_asymmetric(attributes.key_type) {
+                    match basic_client.psa_export_public_key(&key.name) {
+                        Ok(public_key) => {
+                            match spki_sha256(attributes.key_type, attributes.bits, &public_key) {
+                                Ok(fingerprint) => Some(fingerprint),
+                                Err(e) => {
+                                    warn!(
+                                        "Could not compute the fingerprint of \"{}\": {}",
+                                        key.name, e
+                                    );
+                                    None
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Could not export the public key of \"{}\": {}", key.name, e);
+                            None
+                        }
+                    }
+                } else {
+                    None
+                };
+                reports.push(KeyReport {
+                    provider_id: provider.id as u8,
+                    provider: provider.id.to_string(),
+                    name: key.name.clone(),
+                    key_type: key_type_to_string(attributes.key_type),
+                    bits: attributes.bits,
+                    algorithm: algorithm_to_string(attributes.policy.permitted_algorithms),
+                    usage: usage_flags_to_strings(attributes.policy.usage_flags),
+                    spki_sha256,
+                    exportable: attributes.is_exportable(),
+                    findings: policy
+                        .findings(&attributes)
+                        .into_iter()
+                        .map(|rule| Finding {
+                            rule: rule.id.clone(),
+                            severity: rule.severity,
+                            description: rule.description.clone(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        let report = match self.format {
+            ReportFormat::Csv => to_csv(&reports),
+            ReportFormat::Json => {
+                let mut json = serde_json::to_string_pretty(&reports).map_err(|e| {
+                    error!("Could not serialise the report: {}", e);
+                    ToolErrorKind::IncorrectData
+                })?;
+                json.push('\n');
+                json
+            }
+        };
+        match &self.output_file {
+            Some(path) => std::fs::write(path, report)?,
+            None => print!("{}", report),
+        }
+
+        let findings = reports.iter().flat_map(|report| &report.findings);
+        info!(
+            "{} keys reported, with {} findings.",
+            reports.len(),
+            findings.clone().count()
+        );
+        if let Some(threshold) = self.fail_on {
+            let count = findings
+                .filter(|finding| finding.severity >= threshold)
+                .count();
+            if count > 0 {
+                error!(
+                    "{} findings of severity {} or higher",
+                    count,
+                    threshold.as_str()
+                );
+                return Err(ToolErrorKind::AuditFindings.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_csv(reports: &[KeyReport]) -> String {
+    let mut csv = String::from(
+        "provider-id,provider,name,type,bits,algorithm,usage,spki-sha256,exportable,findings\n",
+    );
+    for report in reports {
+        let findings: Vec<String> = report
+            .findings
+            .iter()
+            .map(|finding| format!("{}:{}", finding.rule, finding.severity.as_str()))
+            .collect();
+        let fields = [
+            report.provider_id.to_string(),
+            report.provider.clone(),
+            report.name.clone(),
+            report.key_type.clone(),
+            report.bits.to_string(),
+            report.algorithm.clone(),
+            report.usage.join(" "),
+            report.spki_sha256.clone().unwrap_or_default(),
+            report.exportable.to_string(),
+            findings.join(" "),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// Quotes a CSV field if needed, as described in RFC 4180.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Computes a shared secret with a peer using ECDH.
+//!
+//! The raw shared secret is the output of `psa_raw_key_agreement`. As it is not uniformly distributed,
+//! it should be passed through a key derivation function before being used as a key: `--hkdf` does
+//! that with HKDF (RFC 5869) using the given hash, salt and info.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_format::decode_key;
+use crate::key_ref::KeyRef;
+use crate::key_spec::parse_hash;
+use hkdf::Hkdf;
+use log::{error, info};
+use parsec_client::core::interface::operations::psa_algorithm::{Hash, RawKeyAgreement};
+use parsec_client::core::interface::operations::psa_key_attributes::{EccFamily, Type};
+use parsec_client::BasicClient;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Computes a shared secret with a peer using ECDH.
+#[derive(Debug, StructOpt)]
+pub struct KeyAgreement {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Path of the file containing the public key of the peer: a PEM or DER encoded SubjectPublicKeyInfo
+    /// or the raw public key (uncompressed point for Weierstrass curves, little-endian u-coordinate for
+    /// Montgomery curves).
+    #[structopt(long = "peer-key", parse(from_os_str))]
+    peer_key: PathBuf,
+
+    /// Derive the output from the shared secret with HKDF using this hash algorithm (sha256 or sha384).
+    #[structopt(long = "hkdf")]
+    hkdf: Option<String>,
+
+    /// HKDF salt, in base 64.
+    #[structopt(long = "salt", requires = "hkdf")]
+    salt: Option<String>,
+
+    /// HKDF info, as a UTF-8 string.
+    #[structopt(long = "info", requires = "hkdf")]
+    info: Option<String>,
+
+    /// Length in bytes of the HKDF output. Defaults to the output size of the hash.
+    #[structopt(long = "length", requires = "hkdf")]
+    length: Option<usize>,
+}
+
+impl KeyAgreement {
+    /// Computes a shared secret.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let psa_key_attributes = basic_client.key_attributes(&self.key_name)?;
+        let curve_family = match psa_key_attributes.key_type {
+            Type::EccKeyPair { curve_family } => curve_family,
+            _ => {
+                error!("Key agreement requires an ECC key pair");
+                return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+            }
+        };
+        let bits = psa_key_attributes.bits;
+
+        let input = std::fs::read(&self.peer_key)?;
+        let peer_key =
+            if pem::parse(&input).is_err() && input.len() == raw_length(curve_family, bits) {
+                input
+            } else {
+                let key = decode_key(&input, None)?;
+                if key.key_type != (Type::EccPublicKey { curve_family }) || key.bits != bits {
+                    error!(
+                        "The peer key ({}, {} bits) is not on the curve of key \"{}\"",
+                        key.key_type, key.bits, self.key_name
+                    );
+                    return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                }
+                key.data
+            };
+
+        info!("Computing the shared secret...");
+        let shared_secret =
+            basic_client.psa_raw_key_agreement(RawKeyAgreement::Ecdh, &self.key_name, &peer_key)?;
+
+        let output = match &self.hkdf {
+            Some(hash) => {
+                let salt = match &self.salt {
+                    Some(salt) => Some(base64::decode(salt).map_err(|_| {
+                        error!("The salt is not valid base 64");
+                        ToolErrorKind::IncorrectData
+                    })?),
+                    None => None,
+                };
+                let info_bytes = self.info.as_deref().unwrap_or_default().as_bytes();
+                self.derive(
+                    parse_hash(hash)?,
+                    salt.as_deref(),
+                    &shared_secret,
+                    info_bytes,
+                )?
+            }
+            None => shared_secret,
+        };
+
+        println!("{}", base64::encode(output));
+        Ok(())
+    }
+
+    fn derive(&self, hash: Hash, salt: Option<&[u8]>, ikm: &[u8], info: &[u8]) -> Result<Vec<u8>> {
+        info!("Deriving the output with HKDF-{:?}...", hash);
+        let mut okm;
+        let expanded = match hash {
+            Hash::Sha256 => {
+                okm = vec![0; self.length.unwrap_or(32)];
+                Hkdf::<sha2::Sha256>::new(salt, ikm).expand(info, &mut okm)
+            }
+            Hash::Sha384 => {
+                okm = vec![0; self.length.unwrap_or(48)];
+                Hkdf::<sha2::Sha384>::new(salt, ikm).expand(info, &mut okm)
+            }
+            _ => {
+                error!("HKDF is only supported with SHA-256 and SHA-384");
+                return Err(ToolErrorKind::NotSupported.into());
+            }
+        };
+        expanded.map_err(|_| {
+            error!("The requested HKDF output is too long");
+            ToolErrorKind::IncorrectData
+        })?;
+        Ok(okm)
+    }
+}
+
+// Size of a raw public key on the given curve, as expected by `psa_raw_key_agreement`.
+fn raw_length(curve_family: EccFamily, bits: usize) -> usize {
+    let len = (bits + 7) / 8;
+    if curve_family == EccFamily::Montgomery {
+        len
+    } else {
+        1 + 2 * len
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that a key exists, for scripts.
+//!
+//! Nothing is printed. The exit code is:
+//!
+//! * 0 if the key exists and has the expected type and algorithm,
+//! * 1 if it does not exist,
+//! * 2 if it does not have the expected type or algorithm,
+//! * 4 if the key could not be checked, for example if the service can not be reached. The error is
+//!   logged.
+
+use crate::error::{Error, Result, ToolErrorKind};
+use crate::key_filter::key_type_matches;
+use crate::key_ref::KeyRef;
+use crate::key_spec::parse_algorithm;
+use log::error;
+use parsec_client::core::interface::requests::ResponseStatus;
+use parsec_client::error::{ClientErrorKind, Error as ClientError};
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Checks that a key exists, with the exit code: 0 if it exists and matches the expectations, 1 if it
+/// does not exist, 2 if it does not match the expectations, 4 if it could not be checked.
+#[derive(Debug, StructOpt)]
+pub struct KeyExists {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Expected type of the key, e.g. "rsa-key-pair", "ecc-key-pair(secp-r1)" or "ecc-key-pair" for any
+    /// curve family.
+    #[structopt(long = "expect-type")]
+    expect_type: Option<String>,
+
+    /// Expected permitted algorithm of the key, e.g. "ecdsa(sha256)".
+    #[structopt(long = "expect-algorithm")]
+    expect_algorithm: Option<String>,
+}
+
+impl KeyExists {
+    /// Checks the key.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let expect_algorithm = self
+            .expect_algorithm
+            .as_deref()
+            .map(parse_algorithm)
+            .transpose()?;
+
+        let attributes = match basic_client.key_attributes(&self.key_name) {
+            Ok(attributes) => attributes,
+            Err(ClientError::Client(ClientErrorKind::NotFound))
+            | Err(ClientError::Service(ResponseStatus::PsaErrorDoesNotExist)) => {
+                return Err(ToolErrorKind::KeyDoesNotExist.into())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let type_matches = self.expect_type.as_deref().map_or(true, |expected| {
+            key_type_matches(attributes.key_type, expected)
+        });
+        let algorithm_matches = expect_algorithm.map_or(true, |expected| {
+            attributes.policy.permitted_algorithms == expected
+        });
+        if !type_matches || !algorithm_matches {
+            return Err(ToolErrorKind::KeyMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Turns the errors which are not the result of the check, including the ones of the creation of
+    /// the client, into `KeyCheckFailed` after logging them.
+    pub fn check_failed(&self, e: Error) -> Error {
+        if e.is_check_result() {
+            e
+        } else {
+            error!("Could not check key \"{}\": {} ({:?})", self.key_name, e, e);
+            ToolErrorKind::KeyCheckFailed.into()
+        }
+    }
+}
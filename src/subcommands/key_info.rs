@@ -0,0 +1,168 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shows the attributes of a single key.
+//!
+//! Besides the attributes themselves, the SHA-256 fingerprint of the public key (as a DER
+//! SubjectPublicKeyInfo) is shown for asymmetric keys, together with the parsec-tool
+//! subcommands that the policy of the key allows.
+
+use crate::error::Result;
+use crate::fingerprint::{is_asymmetric, spki_sha256};
+use crate::key_ref::KeyRef;
+use crate::key_spec::{
+    algorithm_to_string, key_type_to_string, lifetime_to_string, usage_flags_to_strings,
+};
+use log::error;
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Algorithm, AsymmetricSignature, Hash, KeyAgreement, RawKeyAgreement, SignHash,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{Attributes, EccFamily, Type};
+use parsec_client::error::{ClientErrorKind, Error as ClientError};
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Shows the attributes of a key.
+#[derive(Debug, StructOpt)]
+pub struct KeyInfo {
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+}
+
+impl KeyInfo {
+    /// Shows the attributes of a key.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let keys = basic_client.list_keys()?;
+        let implicit_provider = basic_client.implicit_provider();
+        // A key with the same name can exist in several providers: prefer the one of the implicit
+        // provider, as it is the one that the other subcommands would use.
+        let key = keys
+            .iter()
+            .filter(|key| key.name == *self.key_name)
+            .min_by_key(|key| key.provider_id != implicit_provider)
+            .ok_or_else(|| {
+                error!("Key \"{}\" does not exist", self.key_name);
+                ClientError::Client(ClientErrorKind::NotFound)
+            })?;
+        let attributes = key.attributes;
+
+        println!("Key \"{}\"", key.name);
+        println!("  Provider:            {}", key.provider_id);
+        println!(
+            "  Lifetime:            {}",
+            lifetime_to_string(attributes.lifetime)
+        );
+        println!(
+            "  Type:                {}",
+            key_type_to_string(attributes.key_type)
+        );
+        println!("  Size:                {} bits", attributes.bits);
+        println!(
+            "  Usage flags:         {}",
+            join(&usage_flags_to_strings(attributes.policy.usage_flags))
+        );
+        println!(
+            "  Permitted algorithm: {}",
+            algorithm_to_string(attributes.policy.permitted_algorithms)
+        );
+        if is_asymmetric(attributes.key_type) {
+            // The key may only exist in another provider than the implicit one.
+            basic_client.set_implicit_provider(key.provider_id);
+            let public_key = basic_client.psa_export_public_key(&self.key_name)?;
+            println!(
+                "  SPKI SHA-256:        {}",
+                spki_sha256(attributes.key_type, attributes.bits, &public_key)?
+            );
+        }
+        println!("  Usable for:          {}", join(&usable_for(&attributes)));
+
+        Ok(())
+    }
+}
+
+fn join(items: &[&str]) -> String {
+    if items.is_empty() {
+        String::from("none")
+    } else {
+        items.join(", ")
+    }
+}
+
+// Lists the subcommands which can be used with a key, following the checks that they do.
+fn usable_for(attributes: &Attributes) -> Vec<&'static str> {
+    let usage_flags = attributes.policy.usage_flags;
+    let key_pair = attributes.key_type.is_ecc_key_pair() || attributes.key_type == Type::RsaKeyPair;
+    let mut operations = Vec::new();
+
+    match attributes.policy.permitted_algorithms {
+        Algorithm::AsymmetricSignature(alg) if key_pair && usage_flags.sign_hash() => {
+            operations.push("sign");
+            if can_create_csr(alg, attributes) {
+                operations.push("create-csr");
+            }
+        }
+        Algorithm::AsymmetricEncryption(_) => {
+            if usage_flags.encrypt() {
+                operations.push("encrypt");
+            }
+            if key_pair && usage_flags.decrypt() {
+                operations.push("decrypt");
+            }
+        }
+        Algorithm::Cipher(_) | Algorithm::Aead(_) => {
+            if usage_flags.encrypt() {
+                operations.push("encrypt");
+            }
+            if usage_flags.decrypt() {
+                operations.push("decrypt");
+            }
+        }
+        Algorithm::KeyAgreement(KeyAgreement::Raw(RawKeyAgreement::Ecdh))
+            if attributes.key_type.is_ecc_key_pair() && usage_flags.derive() =>
+        {
+            operations.push("key-agreement");
+        }
+        _ => (),
+    }
+    if is_asymmetric(attributes.key_type) {
+        operations.push("export-public-key");
+    }
+    if key_pair && usage_flags.export() {
+        operations.push("export-key");
+    }
+    operations.push("delete-key");
+    operations
+}
+
+fn can_create_csr(alg: AsymmetricSignature, attributes: &Attributes) -> bool {
+    match alg {
+        AsymmetricSignature::RsaPkcs1v15Sign { hash_alg } => matches!(
+            hash_alg,
+            SignHash::Any
+                | SignHash::Specific(Hash::Sha256)
+                | SignHash::Specific(Hash::Sha384)
+                | SignHash::Specific(Hash::Sha512)
+        ),
+        AsymmetricSignature::RsaPkcs1v15SignRaw => true,
+        AsymmetricSignature::RsaPss { hash_alg } => matches!(
+            hash_alg,
+            SignHash::Any
+                | SignHash::Specific(Hash::Sha256)
+                | SignHash::Specific(Hash::Sha384)
+                | SignHash::Specific(Hash::Sha512)
+        ),
+        AsymmetricSignature::Ecdsa { hash_alg } => {
+            attributes.key_type
+                == (Type::EccKeyPair {
+                    curve_family: EccFamily::SecpR1,
+                })
+                && matches!(
+                    (hash_alg, attributes.bits),
+                    (SignHash::Any, 256)
+                        | (SignHash::Specific(Hash::Sha256), 256)
+                        | (SignHash::Specific(Hash::Sha384), 384)
+                )
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Manage the local metadata of keys: labels, owner, description and not-after date.
+//!
+//! The metadata is stored by parsec-tool, not by the Parsec service, and applies to the key of the
+//! provider of the command.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::key_metadata::{parse_date, KeyMetadata, MetadataStore};
+use crate::key_ref::KeyRef;
+use log::{error, info};
+use parsec_client::core::interface::requests::ProviderId;
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Manage the local metadata of keys.
+#[derive(Debug, StructOpt)]
+pub enum KeyLabel {
+    /// Set metadata of a key, keeping the fields which are not given.
+    Set(Set),
+
+    /// Print the metadata of a key.
+    Get(Get),
+
+    /// Remove labels of a key, or all its metadata if no label is given.
+    Rm(Rm),
+}
+
+/// Set metadata of a key, keeping the fields which are not given.
+#[derive(Debug, StructOpt)]
+pub struct Set {
+    #[structopt(short = "k", long = "key-name")]
+    key_name: KeyRef,
+
+    /// Label given as "name=value". Can be repeated.
+    #[structopt(short = "l", long = "label")]
+    labels: Vec<String>,
+
+    /// Person or team responsible for the key.
+    #[structopt(long = "owner")]
+    owner: Option<String>,
+
+    /// Why the key exists.
+    #[structopt(long = "description")]
+    description: Option<String>,
+
+    /// Date after which the key should not be used, as YYYY-MM-DD.
+    #[structopt(long = "not-after")]
+    not_after: Option<String>,
+}
+
+/// Print the metadata of a key.
+#[derive(Debug, StructOpt)]
+pub struct Get {
+    #[structopt(short = "k", long = "key-name")]
+    key_name: KeyRef,
+}
+
+/// Remove labels of a key, or all its metadata if no label is given.
+#[derive(Debug, StructOpt)]
+pub struct Rm {
+    #[structopt(short = "k", long = "key-name")]
+    key_name: KeyRef,
+
+    /// Name of a label to remove. Can be repeated.
+    #[structopt(short = "l", long = "label")]
+    labels: Vec<String>,
+}
+
+impl KeyLabel {
+    /// Runs the subcommand.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        match self {
+            KeyLabel::Set(cmd) => cmd.run(basic_client),
+            KeyLabel::Get(cmd) => cmd.run(basic_client),
+            KeyLabel::Rm(cmd) => cmd.run(basic_client),
+        }
+    }
+
+    /// Provider given in the key name, if any.
+    pub fn key_provider(&self) -> Option<ProviderId> {
+        match self {
+            KeyLabel::Set(cmd) => cmd.key_name.provider,
+            KeyLabel::Get(cmd) => cmd.key_name.provider,
+            KeyLabel::Rm(cmd) => cmd.key_name.provider,
+        }
+    }
+}
+
+impl Set {
+    /// Sets metadata.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let provider = basic_client.implicit_provider();
+        if !basic_client
+            .list_keys()?
+            .iter()
+            .any(|key| key.name == *self.key_name && key.provider_id == provider)
+        {
+            error!("Key \"{}\" does not exist in {}", self.key_name, provider);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+
+        let mut store = MetadataStore::load()?;
+        let mut metadata = store
+            .get(&self.key_name, provider)
+            .cloned()
+            .unwrap_or_else(|| KeyMetadata::new(&self.key_name, provider));
+        for label in &self.labels {
+            match label.split_once('=') {
+                Some((name, value)) if !name.is_empty() => {
+                    let _ = metadata.labels.insert(name.to_string(), value.to_string());
+                }
+                _ => {
+                    error!("Invalid label \"{}\", expected \"name=value\"", label);
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+            }
+        }
+        if let Some(owner) = &self.owner {
+            metadata.owner = Some(owner.clone());
+        }
+        if let Some(description) = &self.description {
+            metadata.description = Some(description.clone());
+        }
+        if let Some(not_after) = &self.not_after {
+            metadata.not_after = Some(parse_date(not_after)?);
+        }
+        store.set(metadata);
+        store.save()?;
+
+        info!("Metadata of key \"{}\" updated.", self.key_name);
+        Ok(())
+    }
+}
+
+impl Get {
+    /// Prints metadata.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let store = MetadataStore::load()?;
+        match store.get(&self.key_name, basic_client.implicit_provider()) {
+            Some(metadata) => print_metadata(metadata, ""),
+            None => info!("Key \"{}\" has no metadata.", self.key_name),
+        }
+        Ok(())
+    }
+}
+
+impl Rm {
+    /// Removes metadata.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let provider = basic_client.implicit_provider();
+        let mut store = MetadataStore::load()?;
+        if self.labels.is_empty() {
+            if store.remove(&self.key_name, provider as u8) {
+                store.save()?;
+            }
+            info!("Metadata of key \"{}\" removed.", self.key_name);
+            return Ok(());
+        }
+
+        let mut metadata = match store.get(&self.key_name, provider) {
+            Some(metadata) => metadata.clone(),
+            None => {
+                info!("Key \"{}\" has no metadata.", self.key_name);
+                return Ok(());
+            }
+        };
+        for label in &self.labels {
+            if metadata.labels.remove(label).is_none() {
+                info!("Key \"{}\" has no label \"{}\".", self.key_name, label);
+            }
+        }
+        store.set(metadata);
+        store.save()?;
+
+        info!("Labels of key \"{}\" removed.", self.key_name);
+        Ok(())
+    }
+}
+
+/// Prints the metadata of a key, one field per line starting with `indent`.
+pub fn print_metadata(metadata: &KeyMetadata, indent: &str) {
+    if let Some(owner) = &metadata.owner {
+        println!("{}Owner: {}", indent, owner);
+    }
+    if let Some(description) = &metadata.description {
+        println!("{}Description: {}", indent, description);
+    }
+    if let Some(not_after) = &metadata.not_after {
+        println!("{}Not after: {}", indent, not_after);
+    }
+    for (name, value) in &metadata.labels {
+        println!("{}Label: {}={}", indent, name, value);
+    }
+}
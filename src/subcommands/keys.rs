@@ -0,0 +1,188 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Provision keys from a manifest of the keys which should exist in the service.
+//!
+//! `keys plan` prints the changes needed for the existing keys to match the manifest: keys to create
+//! (`+`), keys whose attributes differ from the manifest (`~`) and keys not listed in the manifest
+//! (`-`). `keys apply` creates the missing keys and, with `--delete-extra`, deletes the keys not listed
+//! in the manifest. Keys which differ from the manifest are never modified, as their attributes can
+//! not be changed: they have to be deleted and created again by hand. Applying a manifest twice does
+//! not change anything the second time.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_manifest::{ChangeKind, KeyChange, Manifest};
+use crate::key_metadata::remove_key_metadata;
+use crate::key_spec::{algorithm_to_string, key_type_to_string};
+use crate::subcommands::create_key::generate_key;
+use crate::util::confirm;
+use log::{error, info, warn};
+use parsec_client::BasicClient;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+/// Provision keys from a manifest.
+#[derive(Debug, StructOpt)]
+pub enum Keys {
+    /// Print the changes needed for the existing keys to match a manifest.
+    Plan(Plan),
+
+    /// Create the keys of a manifest which do not exist, and optionally delete the keys it does not list.
+    Apply(Apply),
+}
+
+/// Print the changes needed for the existing keys to match a manifest.
+#[derive(Debug, StructOpt)]
+pub struct Plan {
+    /// TOML, JSON or YAML manifest of the keys.
+    #[structopt(short = "m", long = "manifest", parse(from_os_str))]
+    manifest: PathBuf,
+}
+
+/// Create the keys of a manifest which do not exist, and optionally delete the keys it does not list.
+#[derive(Debug, StructOpt)]
+pub struct Apply {
+    /// TOML, JSON or YAML manifest of the keys.
+    #[structopt(short = "m", long = "manifest", parse(from_os_str))]
+    manifest: PathBuf,
+
+    /// Delete the keys of the providers used by the manifest which it does not list.
+    #[structopt(long = "delete-extra")]
+    delete_extra: bool,
+
+    /// Do not ask for confirmation before deleting keys.
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+}
+
+impl Keys {
+    /// Runs the subcommand.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        match self {
+            Keys::Plan(cmd) => cmd.run(basic_client),
+            Keys::Apply(cmd) => cmd.run(basic_client),
+        }
+    }
+}
+
+impl Plan {
+    /// Prints the changes.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let changes = plan(&basic_client, &self.manifest)?;
+        print_changes(&changes);
+        Ok(())
+    }
+}
+
+impl Apply {
+    /// Applies the changes.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let changes = plan(&basic_client, &self.manifest)?;
+        print_changes(&changes);
+
+        let policy = GuardrailPolicy::load()?;
+        for change in &changes {
+            match change.kind {
+                ChangeKind::Create(_) => policy.check_provider(change.provider)?,
+                ChangeKind::Extra if self.delete_extra => {
+                    policy.check_provider(change.provider)?;
+                    policy.check_deletion(&change.name, self.yes)?;
+                }
+                _ => (),
+            }
+        }
+
+        let extra: Vec<&KeyChange> = changes
+            .iter()
+            .filter(|change| matches!(change.kind, ChangeKind::Extra))
+            .collect();
+        let delete_extra = self.delete_extra && !extra.is_empty();
+        if delete_extra && !self.yes {
+            confirm(&format!("Delete {} extra keys?", extra.len()))?;
+        }
+
+        for change in &changes {
+            if let ChangeKind::Create(attributes) = change.kind {
+                basic_client.set_implicit_provider(change.provider);
+                generate_key(&basic_client, &change.name, attributes)?;
+            }
+        }
+        if delete_extra {
+            for change in extra {
+                basic_client.set_implicit_provider(change.provider);
+                basic_client.psa_destroy_key(&change.name)?;
+                remove_key_metadata(&change.name, change.provider)?;
+                info!("Key \"{}\" deleted.", change.name);
+            }
+        } else if !extra.is_empty() {
+            info!("Extra keys not deleted.");
+        }
+
+        let drifted = changes
+            .iter()
+            .filter(|change| matches!(change.kind, ChangeKind::Drift(_)))
+            .count();
+        if drifted > 0 {
+            error!(
+                "{} keys differ from the manifest; they have to be deleted and created again",
+                drifted
+            );
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+
+        info!("Keys match the manifest.");
+        Ok(())
+    }
+}
+
+fn plan(basic_client: &BasicClient, manifest: &Path) -> Result<Vec<KeyChange>> {
+    let manifest = Manifest::from_file(manifest)?;
+    manifest.plan(basic_client.implicit_provider(), &basic_client.list_keys()?)
+}
+
+fn print_changes(changes: &[KeyChange]) {
+    let mut counts = [0; 4];
+    for change in changes {
+        match &change.kind {
+            ChangeKind::Unchanged => {
+                counts[0] += 1;
+                info!(
+                    "Key \"{}\" ({}) is up to date.",
+                    change.name, change.provider
+                );
+            }
+            ChangeKind::Create(attributes) => {
+                counts[1] += 1;
+                println!(
+                    "+ {} ({}): {}, {} bits, permitted algorithm: {}",
+                    change.name,
+                    change.provider,
+                    key_type_to_string(attributes.key_type),
+                    attributes.bits,
+                    algorithm_to_string(attributes.policy.permitted_algorithms)
+                );
+            }
+            ChangeKind::Drift(differences) => {
+                counts[2] += 1;
+                println!(
+                    "~ {} ({}): {}",
+                    change.name,
+                    change.provider,
+                    differences.join("; ")
+                );
+            }
+            ChangeKind::Extra => {
+                counts[3] += 1;
+                println!("- {} ({})", change.name, change.provider);
+            }
+        }
+    }
+    info!(
+        "{} keys up to date, {} to create, {} differing from the manifest, {} not in the manifest.",
+        counts[0], counts[1], counts[2], counts[3]
+    );
+    if counts[2] > 0 {
+        warn!("Keys differing from the manifest can not be modified.");
+    }
+}
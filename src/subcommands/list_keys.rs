@@ -2,36 +2,285 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Lists all keys belonging to the application.
+//!
+//! Keys can be filtered on their provider, name, type, permitted algorithm, usage flags and local
+//! metadata. Besides the default format, `--long` prints a table of the attributes of the keys, using
+//! the same syntax as `create-key`, and `--names-only` prints one key name per line for scripts.
+//! `--fingerprint` adds a fingerprint of the public key of asymmetric keys.
 
-use crate::error::Result;
-use log::info;
+use crate::error::{Error, Result};
+use crate::fingerprint::{is_asymmetric, FingerprintKind, Fingerprints};
+use crate::key_filter::{name_pattern, KeyFilter};
+use crate::key_metadata::{parse_date, MetadataStore};
+use crate::key_spec::{
+    algorithm_to_string, key_type_to_string, lifetime_to_string, parse_usage_flag,
+    usage_flags_to_strings,
+};
+use crate::subcommands::key_label::print_metadata;
+use log::{info, warn};
+use parsec_client::core::interface::operations::list_keys::KeyInfo;
+use parsec_client::core::interface::operations::psa_key_attributes::UsageFlags;
 use parsec_client::BasicClient;
+use std::str::FromStr;
 use structopt::StructOpt;
 
 /// Lists all keys belonging to the application.
 #[derive(Debug, StructOpt)]
-pub struct ListKeys {}
+pub struct ListKeys {
+    #[structopt(flatten)]
+    filter: KeyFilter,
+
+    /// Only list the keys whose name matches this glob pattern, e.g. "anta-key-*".
+    #[structopt(long = "name")]
+    name: Option<String>,
+
+    /// Only list the keys with this usage flag, e.g. "sign" or "export". Can be repeated.
+    #[structopt(long = "usage")]
+    usage: Vec<String>,
+
+    /// Sort the keys by name, provider or bits, instead of the order given by the service.
+    #[structopt(long = "sort")]
+    sort: Option<SortKey>,
+
+    /// Print a table of the attributes of the keys, including their lifetime and usage flags.
+    #[structopt(long = "long", conflicts_with = "names-only")]
+    long: bool,
+
+    /// Only print the names of the keys, one per line.
+    #[structopt(long = "names-only", conflicts_with = "metadata")]
+    names_only: bool,
+
+    /// Also print a fingerprint of the asymmetric keys: spki-sha256, jwk, ssh, tlsa or sshfp.
+    #[structopt(long = "fingerprint", conflicts_with = "names-only")]
+    fingerprint: Option<FingerprintKind>,
+
+    /// Print the local metadata of the keys: owner, description, not-after date and labels.
+    #[structopt(long = "metadata")]
+    metadata: bool,
+
+    /// Only list the keys with this label, given as "name" or "name=value". Can be repeated.
+    #[structopt(long = "label")]
+    labels: Vec<String>,
+
+    /// Only list the keys of this owner.
+    #[structopt(long = "owner")]
+    owner: Option<String>,
+
+    /// Only list the keys whose not-after date is before this date (YYYY-MM-DD).
+    #[structopt(long = "expires-before")]
+    expires_before: Option<String>,
+}
+
+/// Order of the listed keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Sort by key name.
+    Name,
+    /// Sort by provider ID, then by key name.
+    Provider,
+    /// Sort by key size, then by key name.
+    Bits,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "name" => Ok(SortKey::Name),
+            "provider" => Ok(SortKey::Provider),
+            "bits" => Ok(SortKey::Bits),
+            _ => Err(format!(
+                "unknown sort key \"{}\", expected name, provider or bits",
+                input
+            )),
+        }
+    }
+}
 
 impl ListKeys {
     /// Lists the available providers supported by the Parsec service.
-    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
-        let keys = basic_client.list_keys()?;
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let name = self
+            .name
+            .as_deref()
+            .map(|name| name_pattern(name, false))
+            .transpose()?;
+        let mut usage = UsageFlags::default();
+        for flag in &self.usage {
+            parse_usage_flag(flag, &mut usage)?;
+        }
+        let usage = usage_flags_to_strings(usage);
+        let expires_before = self.expires_before.as_deref().map(parse_date).transpose()?;
+        let store = if self.metadata
+            || !self.labels.is_empty()
+            || self.owner.is_some()
+            || expires_before.is_some()
+        {
+            MetadataStore::load()?
+        } else {
+            MetadataStore::default()
+        };
 
+        let mut keys: Vec<_> = self
+            .filter
+            .select(basic_client.list_keys()?)?
+            .into_iter()
+            .filter(|key| {
+                let key_usage = usage_flags_to_strings(key.attributes.policy.usage_flags);
+                let metadata = store.get(&key.name, key.provider_id);
+                name.as_ref().map_or(true, |name| name.is_match(&key.name))
+                    && usage.iter().all(|flag| key_usage.contains(flag))
+                    && self
+                        .labels
+                        .iter()
+                        .all(|label| metadata.map_or(false, |metadata| metadata.has_label(label)))
+                    && self.owner.as_ref().map_or(true, |owner| {
+                        metadata.map_or(false, |metadata| metadata.owner.as_ref() == Some(owner))
+                    })
+                    && expires_before.as_ref().map_or(true, |date| {
+                        metadata
+                            .and_then(|metadata| metadata.not_after.as_ref())
+                            .map_or(false, |not_after| not_after < date)
+                    })
+            })
+            .collect();
+        match self.sort {
+            Some(SortKey::Name) => keys.sort_by(|a, b| a.name.cmp(&b.name)),
+            Some(SortKey::Provider) => keys.sort_by(|a, b| {
+                (a.provider_id as u8, &a.name).cmp(&(b.provider_id as u8, &b.name))
+            }),
+            Some(SortKey::Bits) => {
+                keys.sort_by(|a, b| (a.attributes.bits, &a.name).cmp(&(b.attributes.bits, &b.name)))
+            }
+            None => (),
+        }
+
+        if self.names_only {
+            for key in keys {
+                println!("{}", key.name);
+            }
+            return Ok(());
+        }
         if keys.is_empty() {
             info!("No keys currently available.");
             return Ok(());
         }
         info!("Available keys:");
-        for key in keys {
-            println!(
-                "* {} ({}, {:?}, {} bits, permitted algorithm: {:?})",
-                key.name,
-                key.provider_id,
-                key.attributes.key_type,
-                key.attributes.bits,
-                key.attributes.policy.permitted_algorithms
-            );
+
+        let fingerprints = match self.fingerprint {
+            Some(kind) => keys
+                .iter()
+                .map(|key| fingerprint(&mut basic_client, key, kind))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let rows: Vec<Vec<String>> = if self.long {
+            let mut header = vec![
+                "NAME",
+                "PROVIDER",
+                "TYPE",
+                "BITS",
+                "LIFETIME",
+                "ALGORITHM",
+                "USAGE",
+            ];
+            if self.fingerprint.is_some() {
+                header.push("FINGERPRINT");
+            }
+            let mut rows = vec![header.into_iter().map(String::from).collect()];
+            rows.extend(keys.iter().enumerate().map(|(index, key)| {
+                let mut row = vec![
+                    key.name.clone(),
+                    (key.provider_id as u8).to_string(),
+                    key_type_to_string(key.attributes.key_type),
+                    key.attributes.bits.to_string(),
+                    lifetime_to_string(key.attributes.lifetime),
+                    algorithm_to_string(key.attributes.policy.permitted_algorithms),
+                    usage_flags_to_strings(key.attributes.policy.usage_flags).join(","),
+                ];
+                if let Some(fingerprint) = fingerprints.get(index) {
+                    row.push(fingerprint.clone().unwrap_or_else(|| String::from("-")));
+                }
+                row
+            }));
+            rows
+        } else {
+            Vec::new()
+        };
+        let mut widths = vec![0; rows.first().map_or(0, Vec::len)];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        if let Some(header) = rows.first() {
+            print_row(header, &widths);
+        }
+
+        for (index, key) in keys.iter().enumerate() {
+            if self.long {
+                print_row(&rows[index + 1], &widths);
+            } else {
+                println!(
+                    "* {} ({}, {:?}, {} bits, permitted algorithm: {:?})",
+                    key.name,
+                    key.provider_id,
+                    key.attributes.key_type,
+                    key.attributes.bits,
+                    key.attributes.policy.permitted_algorithms
+                );
+                if let Some(fingerprint) = fingerprints.get(index) {
+                    if is_asymmetric(key.attributes.key_type) {
+                        println!("    Fingerprint: {}", fingerprint.as_deref().unwrap_or("-"));
+                    }
+                }
+            }
+            if self.metadata {
+                if let Some(metadata) = store.get(&key.name, key.provider_id) {
+                    print_metadata(metadata, "    ");
+                }
+            }
         }
         Ok(())
     }
 }
+
+// Returns the fingerprint of an asymmetric key, exported from its provider. A fingerprint which can not
+// be computed is only warned about, so that the other keys are still listed.
+fn fingerprint(
+    basic_client: &mut BasicClient,
+    key: &KeyInfo,
+    kind: FingerprintKind,
+) -> Option<String> {
+    if !is_asymmetric(key.attributes.key_type) {
+        return None;
+    }
+    basic_client.set_implicit_provider(key.provider_id);
+    let fingerprints = basic_client
+        .psa_export_public_key(&key.name)
+        .map_err(Error::from)
+        .and_then(|public_key| {
+            Fingerprints::new(key.attributes.key_type, key.attributes.bits, &public_key)
+        });
+    match fingerprints {
+        Ok(fingerprints) => fingerprints.get(kind),
+        Err(e) => {
+            warn!(
+                "Could not compute the fingerprint of \"{}\": {}",
+                key.name, e
+            );
+            None
+        }
+    }
+}
+
+fn print_row(row: &[String], widths: &[usize]) {
+    let cells: Vec<String> = row
+        .iter()
+        .zip(widths.iter())
+        .map(|(cell, width)| format!("{:width$}", cell, width = width))
+        .collect();
+    println!("{}", cells.join("  ").trim_end());
+}
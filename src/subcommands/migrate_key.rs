@@ -0,0 +1,252 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Migrates an exportable key from one provider to another.
+//!
+//! The key is exported from the source provider and imported in the target provider with the same name
+//! and attributes. The migrated key is then checked: the public keys of asymmetric keys must be
+//! identical, and data signed or encrypted by one copy of the key must be verified or decrypted by the
+//! other, when the policy of the key allows it. If the check fails, the migrated key is deleted. The
+//! source key is only deleted when asked to, after a successful check.
+//!
+//! The local metadata of the key (owner, description, not-after date and labels) is copied to the
+//! migrated key, and removed from the source key when it is deleted. Aliases are not migrated: all
+//! the versions of an alias are keys of one provider.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_metadata::{remove_key_metadata, KeyMetadata, MetadataStore};
+use crate::key_ref::KeyRef;
+use crate::util::hash_data;
+use log::{error, info, warn};
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Algorithm, AsymmetricSignature, Hash, SignHash,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{Attributes, Type};
+use parsec_client::core::interface::requests::ProviderId;
+use parsec_client::BasicClient;
+use std::convert::TryInto;
+use structopt::StructOpt;
+
+/// Migrates a key between providers.
+#[derive(Debug, StructOpt)]
+pub struct MigrateKey {
+    #[structopt(short = "k", long = "key-name")]
+    key_name: KeyRef,
+
+    /// The ID of the provider currently holding the key. Defaults to the provider of the key name, or to
+    /// the provider of the command.
+    #[structopt(long = "from-provider")]
+    from_provider: Option<u8>,
+
+    /// The ID of the provider to migrate the key to.
+    #[structopt(long = "to-provider")]
+    to_provider: u8,
+
+    /// Delete the key from the source provider once it has been migrated and checked.
+    #[structopt(long = "delete-source")]
+    delete_source: bool,
+
+    /// Confirm the deletion of the source key, when the guardrail policy requires it.
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+}
+
+impl MigrateKey {
+    /// Migrates a key.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let from = match (self.from_provider, self.key_name.provider) {
+            (Some(id), Some(key_provider)) if provider(id)? != key_provider => {
+                error!(
+                    "The source provider {} does not match the provider of the key name, {}",
+                    id, key_provider
+                );
+                return Err(ToolErrorKind::IncorrectData.into());
+            }
+            (Some(id), _) => provider(id)?,
+            (None, Some(key_provider)) => key_provider,
+            (None, None) => basic_client.implicit_provider(),
+        };
+        let to = provider(self.to_provider)?;
+        if from == to {
+            error!("The source and target providers are the same");
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+        let policy = GuardrailPolicy::load()?;
+        policy.check_provider(from)?;
+        policy.check_provider(to)?;
+        if self.delete_source {
+            policy.check_deletion(&self.key_name, self.yes)?;
+        }
+
+        let keys = basic_client.list_keys()?;
+        let attributes = keys
+            .iter()
+            .find(|key| key.name == *self.key_name && key.provider_id == from)
+            .ok_or_else(|| {
+                error!("Key \"{}\" does not exist in {}", self.key_name, from);
+                ToolErrorKind::IncorrectData
+            })?
+            .attributes;
+        if keys
+            .iter()
+            .any(|key| key.name == *self.key_name && key.provider_id == to)
+        {
+            error!("Key \"{}\" already exists in {}", self.key_name, to);
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+        if !attributes.is_exportable() {
+            error!(
+                "Key \"{}\" can not be migrated: it was created without the export usage flag, so its \
+                 private material can never leave {}. Create a new key in {} and re-issue whatever \
+                 depends on it (certificates, wrapped data...) instead.",
+                self.key_name, from, to
+            );
+            return Err(ToolErrorKind::NotExportable.into());
+        }
+
+        info!("Exporting key \"{}\" from {}...", self.key_name, from);
+        basic_client.set_implicit_provider(from);
+        let data = basic_client.psa_export_key(&self.key_name)?;
+
+        info!("Importing key \"{}\" into {}...", self.key_name, to);
+        basic_client.set_implicit_provider(to);
+        basic_client.psa_import_key(&self.key_name, &data, attributes)?;
+
+        if let Err(e) = self.check(&mut basic_client, from, to, &attributes) {
+            error!("The migrated key does not match the source key, deleting it");
+            basic_client.set_implicit_provider(to);
+            if let Err(destroy_error) = basic_client.psa_destroy_key(&self.key_name) {
+                error!(
+                    "Could not delete the migrated key \"{}\" from {}, which does not match the \
+                     source key and has to be deleted by hand: {}",
+                    self.key_name, to, destroy_error
+                );
+            }
+            return Err(e);
+        }
+
+        let mut store = MetadataStore::load()?;
+        if let Some(metadata) = store.get(&self.key_name, from).cloned() {
+            store.set(KeyMetadata {
+                provider: to as u8,
+                ..metadata
+            });
+            store.save()?;
+        }
+
+        if self.delete_source {
+            info!("Deleting key \"{}\" from {}...", self.key_name, from);
+            basic_client.set_implicit_provider(from);
+            basic_client.psa_destroy_key(&self.key_name)?;
+            remove_key_metadata(&self.key_name, from)?;
+        }
+
+        info!("Key \"{}\" migrated to {}.", self.key_name, to);
+        Ok(())
+    }
+
+    // Checks that the key in the target provider behaves as the key in the source provider.
+    fn check(
+        &self,
+        basic_client: &mut BasicClient,
+        from: ProviderId,
+        to: ProviderId,
+        attributes: &Attributes,
+    ) -> Result<()> {
+        let key_name = &*self.key_name;
+        let usage_flags = attributes.policy.usage_flags;
+        let key_type = attributes.key_type;
+
+        if key_type.is_ecc_key_pair()
+            || key_type.is_ecc_public_key()
+            || key_type == Type::RsaKeyPair
+            || key_type.is_rsa_public_key()
+        {
+            basic_client.set_implicit_provider(from);
+            let source = basic_client.psa_export_public_key(key_name)?;
+            basic_client.set_implicit_provider(to);
+            if basic_client.psa_export_public_key(key_name)? != source {
+                error!("The public keys are different");
+                return Err(ToolErrorKind::IncorrectData.into());
+            }
+            info!("Public keys are identical.");
+        }
+
+        let message = basic_client.psa_generate_random(32)?;
+        match attributes.policy.permitted_algorithms {
+            Algorithm::AsymmetricSignature(alg)
+                if usage_flags.sign_hash() && usage_flags.verify_hash() =>
+            {
+                let hash = match alg {
+                    AsymmetricSignature::RsaPkcs1v15SignRaw => message,
+                    _ => match alg.hash() {
+                        Some(SignHash::Specific(hash)) => hash_data(&message, hash)?,
+                        _ => hash_data(&message, Hash::Sha256)?,
+                    },
+                };
+                basic_client.set_implicit_provider(to);
+                let signature = basic_client.psa_sign_hash(key_name, &hash, alg)?;
+                basic_client.set_implicit_provider(from);
+                basic_client.psa_verify_hash(key_name, &hash, alg, &signature)?;
+                info!("Signature of the migrated key verified by the source key.");
+            }
+            Algorithm::AsymmetricEncryption(alg)
+                if usage_flags.encrypt() && usage_flags.decrypt() =>
+            {
+                basic_client.set_implicit_provider(from);
+                let ciphertext =
+                    basic_client.psa_asymmetric_encrypt(key_name, alg, &message, None)?;
+                basic_client.set_implicit_provider(to);
+                if basic_client.psa_asymmetric_decrypt(key_name, alg, &ciphertext, None)? != message
+                {
+                    error!("The migrated key did not decrypt the data encrypted by the source key");
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+                info!("Data encrypted by the source key decrypted by the migrated key.");
+            }
+            Algorithm::Aead(alg) if usage_flags.encrypt() && usage_flags.decrypt() => {
+                let nonce = basic_client.psa_generate_random(12)?;
+                basic_client.set_implicit_provider(from);
+                let ciphertext =
+                    basic_client.psa_aead_encrypt(key_name, alg, &nonce, &[], &message)?;
+                basic_client.set_implicit_provider(to);
+                if basic_client.psa_aead_decrypt(key_name, alg, &nonce, &[], &ciphertext)?
+                    != message
+                {
+                    error!("The migrated key did not decrypt the data encrypted by the source key");
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+                info!("Data encrypted by the source key decrypted by the migrated key.");
+            }
+            Algorithm::Cipher(alg) if usage_flags.encrypt() && usage_flags.decrypt() => {
+                basic_client.set_implicit_provider(from);
+                let ciphertext =
+                    basic_client.psa_cipher_encrypt(key_name.to_string(), alg, &message)?;
+                basic_client.set_implicit_provider(to);
+                if basic_client.psa_cipher_decrypt(key_name.to_string(), alg, &ciphertext)?
+                    != message
+                {
+                    error!("The migrated key did not decrypt the data encrypted by the source key");
+                    return Err(ToolErrorKind::IncorrectData.into());
+                }
+                info!("Data encrypted by the source key decrypted by the migrated key.");
+            }
+            _ => warn!(
+                "The policy of the key does not allow checking it with a cryptographic operation."
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn provider(id: u8) -> Result<ProviderId> {
+    id.try_into().map_err(|_| {
+        error!(
+            "The provider ID {} does not map with an existing provider",
+            id
+        );
+        ToolErrorKind::IncorrectData.into()
+    })
+}
@@ -3,31 +3,56 @@
 
 //! Subcommand implementations. Interacts with parsec-client-rust.
 
+mod backup;
+pub(crate) mod batch;
 mod create_csr;
 mod create_ecc_key;
+mod create_key;
 mod create_rsa_key;
+mod create_symmetric_key;
 mod decrypt;
 mod delete_client;
 mod delete_key;
+mod delete_keys;
 mod encrypt;
+mod export_key;
 mod export_public_key;
+mod fingerprint;
 mod generate_random;
+mod import_key;
+mod import_pkcs12;
+mod inventory;
+mod key_agreement;
+mod key_exists;
+mod key_info;
+mod key_label;
+mod keys;
 mod list_authenticators;
 mod list_clients;
 mod list_keys;
 mod list_opcodes;
 mod list_providers;
+mod migrate_key;
 mod ping;
+mod restore;
+mod rotate_key;
 mod sign;
 
-use crate::error::{Error::ParsecClientError, Result};
+use crate::error::{Error::ParsecClientError, Result, ToolErrorKind};
 use crate::subcommands::{
-    create_csr::CreateCsr, create_ecc_key::CreateEccKey, create_rsa_key::CreateRsaKey,
-    decrypt::Decrypt, delete_client::DeleteClient, delete_key::DeleteKey, encrypt::Encrypt,
-    export_public_key::ExportPublicKey, generate_random::GenerateRandom,
+    backup::Backup, batch::Batch, create_csr::CreateCsr, create_ecc_key::CreateEccKey,
+    create_key::CreateKey, create_rsa_key::CreateRsaKey, create_symmetric_key::CreateSymmetricKey,
+    decrypt::Decrypt, delete_client::DeleteClient, delete_key::DeleteKey, delete_keys::DeleteKeys,
+    encrypt::Encrypt, export_key::ExportKey, export_public_key::ExportPublicKey,
+    fingerprint::Fingerprint, generate_random::GenerateRandom, import_key::ImportKey,
+    import_pkcs12::ImportPkcs12, inventory::Inventory, key_agreement::KeyAgreement,
+    key_exists::KeyExists, key_info::KeyInfo, key_label::KeyLabel, keys::Keys,
     list_authenticators::ListAuthenticators, list_clients::ListClients, list_keys::ListKeys,
-    list_opcodes::ListOpcodes, list_providers::ListProviders, ping::Ping, sign::Sign,
+    list_opcodes::ListOpcodes, list_providers::ListProviders, migrate_key::MigrateKey, ping::Ping,
+    restore::Restore, rotate_key::RotateKey, sign::Sign,
 };
+use log::error;
+use parsec_client::core::interface::requests::ProviderId;
 use parsec_client::BasicClient;
 use structopt::StructOpt;
 
@@ -58,9 +83,16 @@ pub enum Subcommand {
     /// Create a RSA key pair (2048 bits). Used by default for asymmetric encryption with RSA PKCS#1 v1.5.
     CreateRsaKey(CreateRsaKey),
 
-    /// Create a ECC key pair (curve secp256r1). Used by default for asymmetric signing with ECDSA (SHA-256).
+    /// Create a ECC key pair (curve secp256r1 by default). Used by default for asymmetric signing with ECDSA
+    /// (SHA-256), or for key agreement with ECDH.
     CreateEccKey(CreateEccKey),
 
+    /// Create an AES (256 bits), ChaCha20 or HMAC key. Used by default for authenticated encryption with AES-GCM.
+    CreateSymmetricKey(CreateSymmetricKey),
+
+    /// Create a key with a full specification of its type, size, lifetime, usage flags and permitted algorithm.
+    CreateKey(CreateKey),
+
     /// Decrypt data using the algorithm of the key
     Decrypt(Decrypt),
 
@@ -70,6 +102,9 @@ pub enum Subcommand {
     /// Delete a key.
     DeleteKey(DeleteKey),
 
+    /// Delete all the keys matching name patterns and filters.
+    DeleteKeys(DeleteKeys),
+
     /// Lists all clients currently having data in the service (admin operation).
     ListClients(ListClients),
 
@@ -81,6 +116,51 @@ pub enum Subcommand {
 
     /// Encrypt data using the algorithm of the key
     Encrypt(Encrypt),
+
+    /// Import a PEM or DER encoded key (PKCS#1, PKCS#8, SEC1 or SubjectPublicKeyInfo).
+    ImportKey(ImportKey),
+
+    /// Import the private key of a PKCS#12 file and store its certificate chain.
+    ImportPkcs12(ImportPkcs12),
+
+    /// Export the private key material of an exportable key pair in PEM format.
+    ExportKey(ExportKey),
+
+    /// Compute a shared secret with a peer public key using ECDH, optionally derived with HKDF (base64).
+    KeyAgreement(KeyAgreement),
+
+    /// Show the attributes, fingerprint and possible uses of a key.
+    KeyInfo(KeyInfo),
+
+    /// Back up exportable keys with their attributes into an encrypted archive.
+    Backup(Backup),
+
+    /// Restore the keys of an archive created with backup.
+    Restore(Restore),
+
+    /// Check that a key exists and has the expected attributes, with the exit code (for scripts).
+    KeyExists(KeyExists),
+
+    /// Manage the local metadata of keys: labels, owner, description and not-after date.
+    KeyLabel(KeyLabel),
+
+    /// Report the keys of all providers as CSV or JSON, with the findings of an audit policy.
+    Inventory(Inventory),
+
+    /// Print the fingerprints of a public key, and DNS records containing them.
+    Fingerprint(Fingerprint),
+
+    /// Provision keys from a manifest of the keys which should exist.
+    Keys(Keys),
+
+    /// Create a new version of a key and point its alias to it.
+    RotateKey(RotateKey),
+
+    /// Migrate an exportable key from one provider to another, checking the result.
+    MigrateKey(MigrateKey),
+
+    /// Run a sequence of commands, read one per line, within a single process.
+    Batch(Batch),
 }
 
 impl Subcommand {
@@ -98,13 +178,81 @@ impl Subcommand {
             Subcommand::ExportPublicKey(cmd) => cmd.run(client),
             Subcommand::CreateRsaKey(cmd) => cmd.run(client),
             Subcommand::CreateEccKey(cmd) => cmd.run(client),
+            Subcommand::CreateSymmetricKey(cmd) => cmd.run(client),
+            Subcommand::CreateKey(cmd) => cmd.run(client),
             Subcommand::Sign(cmd) => cmd.run(client),
             Subcommand::Decrypt(cmd) => cmd.run(client),
             Subcommand::DeleteKey(cmd) => cmd.run(client),
+            Subcommand::DeleteKeys(cmd) => cmd.run(client),
             Subcommand::CreateCsr(cmd) => cmd.run(client),
             Subcommand::Encrypt(cmd) => cmd.run(client),
+            Subcommand::ImportKey(cmd) => cmd.run(client),
+            Subcommand::ImportPkcs12(cmd) => cmd.run(client),
+            Subcommand::ExportKey(cmd) => cmd.run(client),
+            Subcommand::KeyAgreement(cmd) => cmd.run(client),
+            Subcommand::KeyInfo(cmd) => cmd.run(client),
+            Subcommand::Backup(cmd) => cmd.run(client),
+            Subcommand::Restore(cmd) => cmd.run(client),
+            Subcommand::KeyExists(cmd) => cmd.run(client),
+            Subcommand::KeyLabel(cmd) => cmd.run(client),
+            Subcommand::Inventory(cmd) => cmd.run(client),
+            Subcommand::Fingerprint(cmd) => cmd.run(client),
+            Subcommand::Keys(cmd) => cmd.run(client),
+            Subcommand::RotateKey(cmd) => cmd.run(client),
+            Subcommand::MigrateKey(cmd) => cmd.run(client),
+            Subcommand::Batch(_) => {
+                error!("Batch sessions can only be run by ParsecToolApp::run");
+                Err(ToolErrorKind::NotSupported.into())
+            }
+        }
+    }
+
+    /// Provider given in the key name of the subcommand, if any. It overrides the provider of the command.
+    pub fn key_provider(&self) -> Option<ProviderId> {
+        match &self {
+            Subcommand::ExportPublicKey(cmd) => cmd.key_name.provider,
+            Subcommand::CreateRsaKey(cmd) => cmd.key_name.provider,
+            Subcommand::CreateEccKey(cmd) => cmd.key_name.provider,
+            Subcommand::CreateSymmetricKey(cmd) => cmd.key_name.provider,
+            Subcommand::CreateKey(cmd) => cmd.key_name.provider,
+            Subcommand::Sign(cmd) => cmd.key_name.provider,
+            Subcommand::Decrypt(cmd) => cmd.key_name.provider,
+            Subcommand::DeleteKey(cmd) => cmd.key_name.provider,
+            Subcommand::CreateCsr(cmd) => cmd.key_name.provider,
+            Subcommand::Encrypt(cmd) => cmd.key_name.provider,
+            Subcommand::ImportKey(cmd) => cmd.key_name.provider,
+            Subcommand::ImportPkcs12(cmd) => cmd.key_name.provider,
+            Subcommand::ExportKey(cmd) => cmd.key_name.provider,
+            Subcommand::KeyAgreement(cmd) => cmd.key_name.provider,
+            Subcommand::KeyInfo(cmd) => cmd.key_name.provider,
+            Subcommand::KeyExists(cmd) => cmd.key_name.provider,
+            Subcommand::KeyLabel(cmd) => cmd.key_provider(),
+            Subcommand::Fingerprint(cmd) => cmd.key_name.provider,
+            Subcommand::RotateKey(cmd) => cmd.key_name.provider,
+            _ => None,
         }
     }
+
+    /// Indicates if the subcommand operates on the provider of the command, which then has to be allowed
+    /// by the guardrail policy. Subcommands working on several providers check them when running.
+    pub fn targets_provider(&self) -> bool {
+        !matches!(
+            &self,
+            Subcommand::Ping(_)
+                | Subcommand::ListProviders(_)
+                | Subcommand::ListAuthenticators(_)
+                | Subcommand::ListOpcodes(_)
+                | Subcommand::ListKeys(_)
+                | Subcommand::ListClients(_)
+                | Subcommand::DeleteClient(_)
+                | Subcommand::DeleteKeys(_)
+                | Subcommand::Inventory(_)
+                | Subcommand::Keys(_)
+                | Subcommand::MigrateKey(_)
+                | Subcommand::Batch(_)
+        )
+    }
+
     /// Indicates if subcommand requires authentication
     fn authentication_required(&self) -> bool {
         // Subcommands below don't need authentication - all others do.
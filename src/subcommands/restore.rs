@@ -0,0 +1,215 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Restores the keys of an archive written by `backup`.
+//!
+//! The keys are imported with their original names and attributes, in the providers they were backed
+//! up from or in the one given by `--to-provider`. Nothing is imported if the archive holds several keys
+//! with the same name for a provider, or if the name of one of the keys is already used in its provider,
+//! unless `--skip-existing` is given.
+//!
+//! A restore is not all-or-nothing: if the import of a key fails, the keys imported before it are kept
+//! and listed, and `--skip-existing` skips them when the restore is run again.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_archive::{ArchivedKey, KeyArchive, Protection};
+use crate::key_ref::KeyRef;
+use crate::util::read_password;
+use log::{error, info, warn};
+use parsec_client::core::interface::operations::psa_algorithm::Algorithm;
+use parsec_client::core::interface::requests::ProviderId;
+use parsec_client::BasicClient;
+use std::convert::TryInto;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Restores the keys of an archive.
+#[derive(Debug, StructOpt)]
+pub struct Restore {
+    /// Path of the archive to read.
+    #[structopt(short = "i", long = "input-file", parse(from_os_str))]
+    input_file: PathBuf,
+
+    /// Password of an archive protected by a password.
+    /// On the command line, the password is visible to the other users of the system and is
+    /// kept in the shell history: prefer --password-file.
+    #[structopt(long = "password")]
+    password: Option<String>,
+
+    /// Path of a file whose first line is the password of an archive protected by a password.
+    #[structopt(
+        long = "password-file",
+        parse(from_os_str),
+        conflicts_with = "password"
+    )]
+    password_file: Option<PathBuf>,
+
+    /// Name of the wrapping key of an archive protected by a wrapping key, if different from the name
+    /// recorded in the archive or if it is in another provider.
+    #[structopt(long = "wrapping-key", conflicts_with_all = &["password", "password-file"])]
+    wrapping_key: Option<KeyRef>,
+
+    /// The ID of the provider to restore all the keys to, instead of the providers they were backed up
+    /// from.
+    #[structopt(long = "to-provider")]
+    to_provider: Option<u8>,
+
+    /// Skip the keys whose name is already used instead of failing.
+    #[structopt(long = "skip-existing")]
+    skip_existing: bool,
+}
+
+impl Restore {
+    /// Restores keys.
+    pub fn run(&self, mut basic_client: BasicClient) -> Result<()> {
+        let archive = KeyArchive::read(&self.input_file)?;
+        let policy = GuardrailPolicy::load()?;
+        let provider = basic_client.implicit_provider();
+
+        let content_key = match archive.protection() {
+            protection @ Protection::Argon2id { .. } => {
+                let password =
+                    read_password(self.password.as_deref(), self.password_file.as_deref())?;
+                let password = password.ok_or_else(|| {
+                    error!("The archive is protected by a password, use --password-file");
+                    ToolErrorKind::NoInput
+                })?;
+                protection.derive_key(&password)?
+            }
+            Protection::WrappingKey {
+                name,
+                encrypted_key,
+            } => {
+                let wrapping_key = self.wrapping_key.as_deref().unwrap_or(name);
+                if let Some(wrapping_provider) =
+                    self.wrapping_key.as_ref().and_then(|key| key.provider)
+                {
+                    policy.check_provider(wrapping_provider)?;
+                    basic_client.set_implicit_provider(wrapping_provider);
+                }
+                let alg = match basic_client
+                    .key_attributes(wrapping_key)?
+                    .policy
+                    .permitted_algorithms
+                {
+                    Algorithm::AsymmetricEncryption(alg) => alg,
+                    other => {
+                        error!(
+                            "Wrapping key's algorithm is {:?} which can not be used for decryption",
+                            other
+                        );
+                        return Err(ToolErrorKind::WrongKeyAlgorithm.into());
+                    }
+                };
+                info!("Decrypting the archive key with \"{}\"...", wrapping_key);
+                let content_key = basic_client.psa_asymmetric_decrypt(
+                    wrapping_key,
+                    alg,
+                    &base64::decode(encrypted_key)?,
+                    None,
+                )?;
+                basic_client.set_implicit_provider(provider);
+                content_key
+            }
+        };
+
+        let keys = archive.open(&content_key)?;
+        let mut targets = Vec::new();
+        for key in &keys {
+            let target = match self.to_provider {
+                Some(id) => id,
+                None => key.provider_id,
+            };
+            let target: ProviderId = target.try_into().map_err(|_| {
+                error!(
+                    "The provider ID {} does not map with an existing provider",
+                    target
+                );
+                ToolErrorKind::IncorrectData
+            })?;
+            policy.check_provider(target)?;
+            targets.push((key.name.as_str(), target));
+        }
+
+        let mut duplicates: Vec<&str> = targets
+            .iter()
+            .enumerate()
+            .filter(|(i, target)| targets[..*i].contains(target))
+            .map(|(_, (name, _))| *name)
+            .collect();
+        duplicates.sort_unstable();
+        duplicates.dedup();
+        if !duplicates.is_empty() {
+            error!(
+                "The archive holds several keys named {} for the same provider",
+                duplicates.join(", ")
+            );
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+
+        let existing: Vec<(String, ProviderId)> = basic_client
+            .list_keys()?
+            .into_iter()
+            .map(|key| (key.name, key.provider_id))
+            .collect();
+        let conflicts: Vec<(&str, ProviderId)> = targets
+            .iter()
+            .copied()
+            .filter(|(name, target)| {
+                existing
+                    .iter()
+                    .any(|(existing, provider)| existing == name && provider == target)
+            })
+            .collect();
+        if !conflicts.is_empty() && !self.skip_existing {
+            error!(
+                "Keys already exist with the names: {}. Delete them or use --skip-existing.",
+                conflicts
+                    .iter()
+                    .map(|(name, target)| format!("{} ({})", name, target))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            return Err(ToolErrorKind::IncorrectData.into());
+        }
+
+        let mut restored = Vec::new();
+        for (key, target) in keys.iter().zip(targets.iter()) {
+            if conflicts.contains(target) {
+                warn!(
+                    "Skipping key \"{}\" which already exists in {}",
+                    key.name, target.1
+                );
+                continue;
+            }
+            info!("Importing key \"{}\" in {}...", key.name, target.1);
+            basic_client.set_implicit_provider(target.1);
+            if let Err(e) = import_key(&basic_client, key) {
+                error!("Could not import key \"{}\" in {}", key.name, target.1);
+                if !restored.is_empty() {
+                    error!(
+                        "These keys were restored and are kept: {}",
+                        restored
+                            .iter()
+                            .map(|(name, target)| format!("{} ({})", name, target))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    );
+                }
+                return Err(e);
+            }
+            restored.push(*target);
+        }
+        basic_client.set_implicit_provider(provider);
+
+        info!("{} key(s) restored.", restored.len());
+        Ok(())
+    }
+}
+
+fn import_key(basic_client: &BasicClient, key: &ArchivedKey) -> Result<()> {
+    let attributes = key.attributes.to_attributes()?;
+    basic_client.psa_import_key(&key.name, &base64::decode(&key.data)?, attributes)?;
+    Ok(())
+}
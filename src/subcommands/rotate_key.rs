@@ -0,0 +1,101 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rotates a key: creates a new version of it with the same attributes, and makes its alias point to
+//! the new version.
+//!
+//! The first rotation of a key `<name>` creates `<name>.v2` and turns `<name>` into an alias, resolved
+//! to the current version by `sign`, `encrypt`, `export-public-key` and `create-csr`. `decrypt` tries
+//! all the versions, from the current one to the oldest. With a retention setting, the oldest versions
+//! are deleted so that only the given number of versions are kept.
+
+use crate::error::{Result, ToolErrorKind};
+use crate::guardrail_policy::GuardrailPolicy;
+use crate::key_alias::{Alias, AliasFile};
+use crate::key_metadata::remove_key_metadata;
+use crate::key_ref::KeyRef;
+use crate::subcommands::create_key::generate_key;
+use log::{error, info};
+use parsec_client::BasicClient;
+use structopt::StructOpt;
+
+/// Rotates a key.
+#[derive(Debug, StructOpt)]
+pub struct RotateKey {
+    /// Name of the key, which is also the name of its alias.
+    #[structopt(short = "k", long = "key-name")]
+    pub(crate) key_name: KeyRef,
+
+    /// Number of versions to keep, including the new one; older versions are deleted. The setting is
+    /// kept for the next rotations. All versions are kept by default.
+    #[structopt(long = "retention")]
+    retention: Option<usize>,
+
+    /// Confirm the deletion of the old versions, when the guardrail policy requires it.
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+}
+
+impl RotateKey {
+    /// Rotates a key.
+    pub fn run(&self, basic_client: BasicClient) -> Result<()> {
+        let provider = basic_client.implicit_provider();
+        let mut aliases = AliasFile::load()?;
+        let mut alias = aliases
+            .get(&self.key_name, provider)
+            .cloned()
+            .unwrap_or_else(|| Alias::new(&self.key_name, provider));
+        if let Some(retention) = self.retention {
+            if retention == 0 {
+                error!("At least one version of the key has to be kept");
+                return Err(ToolErrorKind::IncorrectData.into());
+            }
+            alias.retention = Some(retention);
+        }
+
+        let policy = GuardrailPolicy::load()?;
+        policy.check_protected(&alias.name)?;
+        let pruned = alias.retention.map_or(0, |retention| {
+            (alias.versions.len() + 1).saturating_sub(retention)
+        });
+        for old_version in &alias.versions[..pruned] {
+            policy.check_deletion(old_version, self.yes)?;
+        }
+
+        let attributes = basic_client.key_attributes(alias.current())?;
+        if attributes.key_type.is_public_key() {
+            error!("Public keys can not be rotated, import the new public key instead");
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+
+        let new_version = alias.next_version();
+        info!(
+            "Creating key \"{}\" with the attributes of \"{}\"...",
+            new_version,
+            alias.current()
+        );
+        generate_key(&basic_client, &new_version, attributes)?;
+        alias.version += 1;
+        alias.versions.push(new_version);
+        aliases.set(alias.clone());
+        aliases.save()?;
+        info!(
+            "Alias \"{}\" now refers to \"{}\".",
+            alias.name,
+            alias.current()
+        );
+
+        if let Some(retention) = alias.retention {
+            while alias.versions.len() > retention {
+                let old_version = alias.versions.remove(0);
+                basic_client.psa_destroy_key(&old_version)?;
+                aliases.set(alias.clone());
+                aliases.save()?;
+                remove_key_metadata(&old_version, provider)?;
+                info!("Old version \"{}\" deleted.", old_version);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -6,6 +6,9 @@
 //! Will use the algorithm set to the key's policy during creation.
 
 use crate::error::Result;
+use crate::key_alias::resolve_key_name;
+use crate::key_ref::KeyRef;
+use crate::key_spec::parse_hash;
 use crate::util::sign_message_with_policy;
 use parsec_client::BasicClient;
 use structopt::StructOpt;
@@ -14,7 +17,12 @@ use structopt::StructOpt;
 #[derive(Debug, StructOpt)]
 pub struct Sign {
     #[structopt(short = "k", long = "key-name")]
-    key_name: String,
+    pub(crate) key_name: KeyRef,
+
+    /// Hash algorithm to use if the key's policy allows any hash algorithm: sha224, sha256, sha384 or
+    /// sha512. Keys with raw RSA PKCS#1 v1.5 signatures use SHA-256 if it is not given.
+    #[structopt(long = "hash")]
+    hash: Option<String>,
 
     /// String of UTF-8 text
     input_data: String,
@@ -23,12 +31,10 @@ pub struct Sign {
 impl Sign {
     /// Signs data.
     pub fn run(&self, basic_client: BasicClient) -> Result<()> {
-        let signature = sign_message_with_policy(
-            &basic_client,
-            &self.key_name,
-            self.input_data.as_bytes(),
-            None,
-        )?;
+        let hash = self.hash.as_deref().map(parse_hash).transpose()?;
+        let key_name = resolve_key_name(&basic_client, &self.key_name)?;
+        let signature =
+            sign_message_with_policy(&basic_client, &key_name, self.input_data.as_bytes(), hash)?;
 
         let signature = base64::encode(signature);
 
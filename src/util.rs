@@ -3,13 +3,22 @@
 
 //! Utility code that is shared by multiple subcommands;
 
+use crate::common::PROJECT_NAME;
 use crate::error::{Result, ToolErrorKind};
+use is_terminal::IsTerminal;
 use log::{error, info};
-use parsec_client::core::interface::operations::psa_algorithm::{Algorithm, Hash, SignHash};
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Algorithm, AsymmetricSignature, Hash, SignHash,
+};
 use parsec_client::BasicClient;
 use picky_asn1::wrapper::IntegerAsn1;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha2::digest::{Digest, DynDigest};
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 struct EccSignature {
@@ -22,6 +31,9 @@ struct EccSignature {
 ///
 /// If the signing key allows for the use of any hashing algorithm, then a default hash can optionally be passed
 /// by the caller, and this hash will be used (otherwise the function will fail).
+///
+/// Keys with the raw RSA PKCS#1 v1.5 algorithm sign the DigestInfo of the message hashed with the default
+/// hash, or SHA-256, which gives the same signature as RSA PKCS#1 v1.5 with that hash.
 pub fn sign_message_with_policy(
     basic_client: &BasicClient,
     key_name: &str,
@@ -45,6 +57,10 @@ pub fn sign_message_with_policy(
                         return Err(ToolErrorKind::NotSupported.into());
                     }
                 }
+                None if alg == AsymmetricSignature::RsaPkcs1v15SignRaw => {
+                    let hash = default_hash.unwrap_or(Hash::Sha256);
+                    digest_info(hash, &hash_data(msg, hash)?)?
+                }
                 _ => {
                     error!("Asymmetric signing algorithm ({:?}) is not supported", alg);
                     return Err(ToolErrorKind::NotSupported.into());
@@ -75,7 +91,8 @@ pub fn sign_message_with_policy(
     Ok(signature)
 }
 
-fn hash_data(data: &[u8], alg: Hash) -> Result<Vec<u8>> {
+/// Hashes data locally with one of the SHA-2 algorithms.
+pub fn hash_data(data: &[u8], alg: Hash) -> Result<Vec<u8>> {
     let mut hasher: Box<dyn DynDigest> = match alg {
         Hash::Sha224 => Box::from(sha2::Sha224::new()),
         Hash::Sha256 => Box::from(sha2::Sha256::new()),
@@ -90,3 +107,158 @@ fn hash_data(data: &[u8], alg: Hash) -> Result<Vec<u8>> {
     hasher.update(data);
     Ok(hasher.finalize().to_vec())
 }
+
+// Encodes the DigestInfo structure signed by RSA PKCS#1 v1.5, made of the identifier of the hash algorithm
+// and of the digest.
+fn digest_info(alg: Hash, digest: &[u8]) -> Result<Vec<u8>> {
+    let prefix: &[u8] = match alg {
+        Hash::Sha224 => &[
+            0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x04, 0x05, 0x00, 0x04, 0x1c,
+        ],
+        Hash::Sha256 => &[
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x01, 0x05, 0x00, 0x04, 0x20,
+        ],
+        Hash::Sha384 => &[
+            0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x02, 0x05, 0x00, 0x04, 0x30,
+        ],
+        Hash::Sha512 => &[
+            0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x03, 0x05, 0x00, 0x04, 0x40,
+        ],
+        _ => {
+            error!("Hashing algorithm ({:?}) not supported", alg);
+            return Err(ToolErrorKind::NotSupported.into());
+        }
+    };
+    Ok([prefix, digest].concat())
+}
+
+/// Asks for the confirmation of a destructive operation on the standard input, failing if it is not
+/// given. Any answer other than "y" or "yes" is a refusal.
+///
+/// Fails if the standard input is not a terminal, or within a batch session, where the standard input
+/// may hold the commands of the session: `--yes` has to be used instead.
+pub fn confirm(prompt: &str) -> Result<()> {
+    if crate::subcommands::batch::in_session() {
+        error!("Confirmation can not be asked within a batch session, use --yes");
+        return Err(ToolErrorKind::NoInput.into());
+    }
+    if !std::io::stdin().is_terminal() {
+        error!("Confirmation can not be asked as the standard input is not a terminal, use --yes");
+        return Err(ToolErrorKind::NoInput.into());
+    }
+    eprint!("{} [y/N] ", prompt);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    let _ = std::io::stdin().lock().read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(())
+    } else {
+        error!("Not confirmed, nothing was done");
+        Err(ToolErrorKind::NotConfirmed.into())
+    }
+}
+
+/// Returns the password given on the command line, or the one read from a password file. Only the
+/// first line of the file is used, without its line ending.
+pub fn read_password(
+    password: Option<&str>,
+    password_file: Option<&Path>,
+) -> Result<Option<String>> {
+    match (password, password_file) {
+        (Some(password), _) => Ok(Some(String::from(password))),
+        (None, Some(path)) => {
+            let contents = std::fs::read_to_string(path).map_err(|e| {
+                error!("Could not read the password file {}: {}", path.display(), e);
+                e
+            })?;
+            Ok(Some(String::from(contents.lines().next().unwrap_or(""))))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Returns the directory holding the local files of parsec-tool: `$XDG_CONFIG_HOME/parsec-tool`, or
+/// `$HOME/.config/parsec-tool` if `XDG_CONFIG_HOME` is not set. Returns `None` if neither is set.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            PathBuf::from(std::env::var_os("HOME").filter(|home| !home.is_empty())?).join(".config")
+        }
+    };
+    Some(base.join(PROJECT_NAME))
+}
+
+/// Reads a TOML file of the configuration directory, such as the key metadata or the aliases. The
+/// default value is returned if the file does not exist yet, or if there is no configuration directory.
+pub fn load_config_file<T: DeserializeOwned + Default>(file_name: &str) -> Result<T> {
+    let path = match config_dir() {
+        Some(dir) => dir.join(file_name),
+        None => return Ok(T::default()),
+    };
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    toml::from_str(&std::fs::read_to_string(&path)?).map_err(|e| {
+        error!("Could not parse {}: {}", path.display(), e);
+        ToolErrorKind::IncorrectData.into()
+    })
+}
+
+/// Writes a TOML file of the configuration directory, creating the directory if needed.
+pub fn store_config_file<T: Serialize>(file_name: &str, value: &T) -> Result<()> {
+    let path = match config_dir() {
+        Some(dir) => dir.join(file_name),
+        None => {
+            error!(
+                "Neither XDG_CONFIG_HOME nor HOME is set, can not locate {}",
+                file_name
+            );
+            return Err(ToolErrorKind::NoInput.into());
+        }
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let contents = toml::to_string(value).map_err(|e| {
+        error!("Could not serialise {}: {}", file_name, e);
+        ToolErrorKind::IncorrectData
+    })?;
+    write_file_atomically(&path, contents.as_bytes(), 0o666)
+}
+
+/// Replaces the contents of a file, through a temporary file in the same directory renamed over it, so
+/// that the file is never left partially written. The file is created with the given permissions,
+/// restricted by the umask.
+pub fn write_file_atomically(path: &Path, contents: &[u8], mode: u32) -> Result<()> {
+    let temp_path = temp_path(path);
+    // A temporary file left by an earlier failure would keep its permissions.
+    let _ = std::fs::remove_file(&temp_path);
+    let result = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// Returns the path of the temporary file used to write a file: `.<file name>.tmp` in the same
+/// directory.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = std::ffi::OsString::from(".");
+    file_name.push(path.file_name().unwrap_or_default());
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
@@ -0,0 +1,392 @@
+// Copyright 2023 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parsing and formatting of key types, algorithms, usage flags and lifetimes.
+
+use parsec_client::core::interface::operations::psa_algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricEncryption, AsymmetricSignature, Cipher,
+    FullLengthMac, Hash, KeyAgreement, KeyDerivation, Mac, RawKeyAgreement, SignHash,
+};
+use parsec_client::core::interface::operations::psa_key_attributes::{
+    DhFamily, EccFamily, Lifetime, Type, UsageFlags,
+};
+use parsec_tool::key_spec::{
+    algorithm_to_string, key_type_to_string, lifetime_to_string, parse_algorithm, parse_key_type,
+    parse_lifetime, parse_usage_flag, usage_flags_to_strings, KeyTemplate,
+};
+
+// Checks that an algorithm is formatted as `text`, and that `text` is parsed back to it.
+fn assert_algorithm(algorithm: Algorithm, text: &str) {
+    assert_eq!(algorithm_to_string(algorithm), text);
+    assert_eq!(parse_algorithm(text).unwrap(), algorithm, "{}", text);
+}
+
+fn assert_key_type(key_type: Type, text: &str) {
+    assert_eq!(key_type_to_string(key_type), text);
+    assert_eq!(parse_key_type(text).unwrap(), key_type, "{}", text);
+}
+
+#[test]
+#[allow(deprecated)]
+fn key_types() {
+    assert_key_type(Type::RawData, "raw-data");
+    assert_key_type(Type::Hmac, "hmac");
+    assert_key_type(Type::Derive, "derive");
+    assert_key_type(Type::Aes, "aes");
+    assert_key_type(Type::Des, "des");
+    assert_key_type(Type::Camellia, "camellia");
+    assert_key_type(Type::Arc4, "arc4");
+    assert_key_type(Type::Chacha20, "chacha20");
+    assert_key_type(Type::RsaPublicKey, "rsa-public-key");
+    assert_key_type(Type::RsaKeyPair, "rsa-key-pair");
+    for (curve_family, name) in [
+        (EccFamily::SecpK1, "secp-k1"),
+        (EccFamily::SecpR1, "secp-r1"),
+        (EccFamily::SecpR2, "secp-r2"),
+        (EccFamily::SectK1, "sect-k1"),
+        (EccFamily::SectR1, "sect-r1"),
+        (EccFamily::SectR2, "sect-r2"),
+        (EccFamily::BrainpoolPR1, "brainpool-p-r1"),
+        (EccFamily::Frp, "frp"),
+        (EccFamily::Montgomery, "montgomery"),
+    ] {
+        assert_key_type(
+            Type::EccKeyPair { curve_family },
+            &format!("ecc-key-pair({})", name),
+        );
+        assert_key_type(
+            Type::EccPublicKey { curve_family },
+            &format!("ecc-public-key({})", name),
+        );
+    }
+    assert_key_type(
+        Type::DhKeyPair {
+            group_family: DhFamily::Rfc7919,
+        },
+        "dh-key-pair(rfc7919)",
+    );
+    assert_key_type(
+        Type::DhPublicKey {
+            group_family: DhFamily::Rfc7919,
+        },
+        "dh-public-key(rfc7919)",
+    );
+
+    assert_eq!(
+        parse_key_type(" ECC-Key-Pair( SECP-R1 ) ").unwrap(),
+        Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1
+        }
+    );
+    assert_eq!(parse_key_type("aes()").unwrap(), Type::Aes);
+    assert!(parse_key_type("ecc-key-pair").is_err());
+    assert!(parse_key_type("ecc-key-pair()").is_err());
+    assert!(parse_key_type("ecc-key-pair(secp-r1").is_err());
+    assert!(parse_key_type("rsa").is_err());
+}
+
+#[test]
+#[allow(deprecated)]
+fn hashes() {
+    for (hash, name) in [
+        (Hash::Md2, "md2"),
+        (Hash::Md4, "md4"),
+        (Hash::Md5, "md5"),
+        (Hash::Ripemd160, "ripemd160"),
+        (Hash::Sha1, "sha1"),
+        (Hash::Sha224, "sha224"),
+        (Hash::Sha256, "sha256"),
+        (Hash::Sha384, "sha384"),
+        (Hash::Sha512, "sha512"),
+        (Hash::Sha512_224, "sha512-224"),
+        (Hash::Sha512_256, "sha512-256"),
+        (Hash::Sha3_224, "sha3-224"),
+        (Hash::Sha3_256, "sha3-256"),
+        (Hash::Sha3_384, "sha3-384"),
+        (Hash::Sha3_512, "sha3-512"),
+    ] {
+        assert_algorithm(Algorithm::Hash(hash), name);
+    }
+    assert_eq!(
+        parse_algorithm("SHA-256").unwrap(),
+        Algorithm::Hash(Hash::Sha256)
+    );
+}
+
+#[test]
+fn macs() {
+    assert_algorithm(
+        Mac::FullLength(FullLengthMac::Hmac {
+            hash_alg: Hash::Sha256,
+        })
+        .into(),
+        "hmac(sha256)",
+    );
+    assert_algorithm(
+        Mac::Truncated {
+            mac_alg: FullLengthMac::Hmac {
+                hash_alg: Hash::Sha384,
+            },
+            mac_length: 16,
+        }
+        .into(),
+        "hmac(sha384,16)",
+    );
+    assert_algorithm(Mac::FullLength(FullLengthMac::CbcMac).into(), "cbc-mac");
+    assert_algorithm(
+        Mac::Truncated {
+            mac_alg: FullLengthMac::CbcMac,
+            mac_length: 8,
+        }
+        .into(),
+        "cbc-mac(8)",
+    );
+    assert_algorithm(Mac::FullLength(FullLengthMac::Cmac).into(), "cmac");
+    assert_algorithm(
+        Mac::Truncated {
+            mac_alg: FullLengthMac::Cmac,
+            mac_length: 12,
+        }
+        .into(),
+        "cmac(12)",
+    );
+
+    assert_eq!(
+        parse_algorithm("cmac()").unwrap(),
+        Mac::FullLength(FullLengthMac::Cmac).into()
+    );
+    assert_eq!(
+        parse_algorithm("hmac(sha256, 16)").unwrap(),
+        parse_algorithm("hmac(sha256,16)").unwrap()
+    );
+    assert!(parse_algorithm("hmac").is_err());
+    assert!(parse_algorithm("hmac(sha256,)").is_err());
+    assert!(parse_algorithm("cmac(sixteen)").is_err());
+}
+
+#[test]
+fn ciphers() {
+    for (cipher, name) in [
+        (Cipher::StreamCipher, "stream-cipher"),
+        (Cipher::Ctr, "ctr"),
+        (Cipher::Cfb, "cfb"),
+        (Cipher::Ofb, "ofb"),
+        (Cipher::Xts, "xts"),
+        (Cipher::EcbNoPadding, "ecb-no-padding"),
+        (Cipher::CbcNoPadding, "cbc-no-padding"),
+        (Cipher::CbcPkcs7, "cbc-pkcs7"),
+    ] {
+        assert_algorithm(cipher.into(), name);
+    }
+}
+
+#[test]
+fn aeads() {
+    for (aead_alg, name) in [
+        (AeadWithDefaultLengthTag::Ccm, "ccm"),
+        (AeadWithDefaultLengthTag::Gcm, "gcm"),
+        (
+            AeadWithDefaultLengthTag::Chacha20Poly1305,
+            "chacha20-poly1305",
+        ),
+    ] {
+        assert_algorithm(Aead::AeadWithDefaultLengthTag(aead_alg).into(), name);
+        assert_algorithm(
+            Aead::AeadWithShortenedTag {
+                aead_alg,
+                tag_length: 8,
+            }
+            .into(),
+            &format!("{}(8)", name),
+        );
+        // An empty argument list is the same as no argument list.
+        assert_eq!(
+            parse_algorithm(&format!("{}()", name)).unwrap(),
+            Aead::AeadWithDefaultLengthTag(aead_alg).into()
+        );
+    }
+    assert!(parse_algorithm("gcm(8,8)").is_err());
+}
+
+#[test]
+fn asymmetric_signatures() {
+    for (hash_alg, hash) in [
+        (SignHash::Any, "any"),
+        (SignHash::Specific(Hash::Sha256), "sha256"),
+        (SignHash::Specific(Hash::Sha512), "sha512"),
+    ] {
+        assert_algorithm(
+            AsymmetricSignature::RsaPkcs1v15Sign { hash_alg }.into(),
+            &format!("rsa-pkcs1v15-sign({})", hash),
+        );
+        assert_algorithm(
+            AsymmetricSignature::RsaPss { hash_alg }.into(),
+            &format!("rsa-pss({})", hash),
+        );
+        assert_algorithm(
+            AsymmetricSignature::Ecdsa { hash_alg }.into(),
+            &format!("ecdsa({})", hash),
+        );
+        assert_algorithm(
+            AsymmetricSignature::DeterministicEcdsa { hash_alg }.into(),
+            &format!("deterministic-ecdsa({})", hash),
+        );
+    }
+    assert_algorithm(
+        AsymmetricSignature::RsaPkcs1v15SignRaw.into(),
+        "rsa-pkcs1v15-sign-raw",
+    );
+    assert_algorithm(AsymmetricSignature::EcdsaAny.into(), "ecdsa-any");
+
+    assert_eq!(
+        parse_algorithm("RSA-PSS( Any )").unwrap(),
+        AsymmetricSignature::RsaPss {
+            hash_alg: SignHash::Any
+        }
+        .into()
+    );
+    assert!(parse_algorithm("ecdsa").is_err());
+    assert!(parse_algorithm("ecdsa()").is_err());
+    assert!(parse_algorithm("ecdsa(sha256").is_err());
+    assert!(parse_algorithm("ecdsa(sha256))").is_err());
+}
+
+#[test]
+fn asymmetric_encryptions() {
+    assert_algorithm(
+        AsymmetricEncryption::RsaPkcs1v15Crypt.into(),
+        "rsa-pkcs1v15-crypt",
+    );
+    assert_algorithm(
+        AsymmetricEncryption::RsaOaep {
+            hash_alg: Hash::Sha256,
+        }
+        .into(),
+        "rsa-oaep(sha256)",
+    );
+    assert!(parse_algorithm("rsa-oaep(any)").is_err());
+}
+
+#[test]
+fn key_agreements_and_derivations() {
+    for (ka_alg, name) in [
+        (RawKeyAgreement::Ecdh, "ecdh"),
+        (RawKeyAgreement::Ffdh, "ffdh"),
+    ] {
+        assert_algorithm(KeyAgreement::Raw(ka_alg).into(), name);
+        for (kdf_alg, kdf) in [
+            (
+                KeyDerivation::Hkdf {
+                    hash_alg: Hash::Sha256,
+                },
+                "hkdf(sha256)",
+            ),
+            (
+                KeyDerivation::Tls12Prf {
+                    hash_alg: Hash::Sha384,
+                },
+                "tls12-prf(sha384)",
+            ),
+            (
+                KeyDerivation::Tls12PskToMs {
+                    hash_alg: Hash::Sha256,
+                },
+                "tls12-psk-to-ms(sha256)",
+            ),
+        ] {
+            assert_algorithm(
+                KeyAgreement::WithKeyDerivation { ka_alg, kdf_alg }.into(),
+                &format!("{}({})", name, kdf),
+            );
+            assert_algorithm(kdf_alg.into(), kdf);
+        }
+    }
+    assert_eq!(
+        parse_algorithm("ecdh()").unwrap(),
+        KeyAgreement::Raw(RawKeyAgreement::Ecdh).into()
+    );
+    assert!(parse_algorithm("ecdh(hkdf)").is_err());
+    assert!(parse_algorithm("hkdf").is_err());
+}
+
+#[test]
+fn none_and_unknown_algorithms() {
+    assert_algorithm(Algorithm::None, "none");
+    assert!(parse_algorithm("").is_err());
+    assert!(parse_algorithm("rsa").is_err());
+    assert!(parse_algorithm("gcm)(").is_err());
+}
+
+#[test]
+fn usage_flags() {
+    let mut usage_flags = UsageFlags::default();
+    for flag in [
+        "sign", "verify", "encrypt", "Decrypt", "export", "copy", "cache", "derive",
+    ] {
+        parse_usage_flag(flag, &mut usage_flags).unwrap();
+    }
+    assert_eq!(
+        usage_flags_to_strings(usage_flags),
+        [
+            "sign-hash",
+            "verify-hash",
+            "sign-message",
+            "verify-message",
+            "encrypt",
+            "decrypt",
+            "export",
+            "copy",
+            "cache",
+            "derive"
+        ]
+    );
+    assert!(parse_usage_flag("unwrap", &mut usage_flags).is_err());
+}
+
+#[test]
+fn lifetimes() {
+    for (lifetime, name) in [
+        (Lifetime::Persistent, "persistent"),
+        (Lifetime::Volatile, "volatile"),
+        (Lifetime::Custom(0x8001), "32769"),
+    ] {
+        assert_eq!(lifetime_to_string(lifetime), name);
+        assert_eq!(parse_lifetime(name).unwrap(), lifetime);
+    }
+    assert!(parse_lifetime("forever").is_err());
+}
+
+#[test]
+fn templates() {
+    let template: KeyTemplate = toml::from_str(
+        r#"
+        type = "ecc-key-pair(secp-r1)"
+        bits = 384
+        usage = ["sign", "verify"]
+        algorithm = "ecdsa(sha384)"
+        "#,
+    )
+    .unwrap();
+    let attributes = template.to_attributes().unwrap();
+    assert_eq!(attributes.lifetime, Lifetime::Persistent);
+    assert_eq!(
+        KeyTemplate::from_attributes(&attributes)
+            .to_attributes()
+            .unwrap(),
+        attributes
+    );
+
+    // The size defaults to the one of the key type.
+    let template = KeyTemplate {
+        key_type: Some(String::from("rsa-key-pair")),
+        algorithm: Some(String::from("rsa-oaep(sha256)")),
+        ..Default::default()
+    };
+    assert_eq!(template.to_attributes().unwrap().bits, 2048);
+
+    let template = KeyTemplate {
+        key_type: Some(String::from("rsa-key-pair")),
+        ..Default::default()
+    };
+    assert!(template.to_attributes().is_err());
+}
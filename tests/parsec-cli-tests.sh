@@ -100,8 +100,45 @@ test_crypto_provider() {
     test_signing "ECC"
     test_csr "RSA"
     test_csr "ECC"
+    if run_cmd $PARSEC_TOOL_CMD list-opcodes 2>/dev/null | grep -q "PsaAeadEncrypt"; then
+        test_aead "AES" ""
+        test_aead "ChaCha20" "--chacha20"
+    else
+        echo "This provider doesn't support AEAD encryption"
+    fi
+    if run_cmd $PARSEC_TOOL_CMD list-opcodes 2>/dev/null | grep -q "PsaCipherEncrypt"; then
+        test_cipher "cbc-pkcs7"
+        test_cipher "ctr"
+    else
+        echo "This provider doesn't support cipher encryption"
+    fi
     test_rsa_key_bits
     test_rsa_key_bits 1024
+    test_rsa_scheme "pss" "sha384"
+    test_rsa_scheme "raw" ""
+    test_rsa_oaep_hash "sha1"
+    test_ecc_curve "P-384" "secp384r1" "sha384"
+    test_ecc_curve "P-256" "prime256v1" "any"
+    test_key_agreement "P-256" "EC -pkeyopt ec_paramgen_curve:P-256"
+    test_key_agreement "X25519" "X25519"
+    test_import_key "RSA"
+    test_import_key "ECC"
+    test_import_key "X25519"
+    test_import_pkcs12
+    test_create_key
+    test_key_info
+    test_batch
+    test_backup
+    test_delete_keys
+    test_keys_manifest
+    test_rotate_key
+    test_key_label
+    test_inventory
+    test_list_keys
+    test_fingerprint
+    test_key_exists
+    test_key_uri $1
+    test_guardrail_policy $1
 }
 
 test_encryption() {
@@ -178,6 +215,72 @@ test_decryption() {
     delete_key "RSA" $KEY
 }
 
+test_aead() {
+# $1 - key type
+# $2 - extra create-symmetric-key arguments
+    KEY="anta-key-aead"
+    TEST_STR="$(date) Parsec AEAD test"
+    AAD="anta-aad"
+
+    echo
+    echo "- Creating an $1 key"
+    run_cmd $PARSEC_TOOL_CMD create-symmetric-key --key-name $KEY $2
+
+    echo
+    echo "- Encrypting and decrypting \"$TEST_STR\" string using the $1 key"
+    run_cmd $PARSEC_TOOL_CMD encrypt "$TEST_STR" --key-name $KEY --aad $AAD >${MY_TMP}/${KEY}.enc
+    debug cat ${MY_TMP}/${KEY}.enc
+    run_cmd $PARSEC_TOOL_CMD decrypt $(cat ${MY_TMP}/${KEY}.enc) --key-name $KEY --aad $AAD \
+            >${MY_TMP}/${KEY}.enc_str
+    if [ "$(cat ${MY_TMP}/${KEY}.enc_str)" != "$TEST_STR" ]; then
+        echo "Error: The result is different from the initial string"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Checking that decryption fails with different additional data"
+    if $PARSEC_TOOL_CMD decrypt $(cat ${MY_TMP}/${KEY}.enc) --key-name $KEY --aad "other" \
+       >/dev/null 2>&1; then
+        echo "Error: Decryption should fail with different additional data"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "$1" $KEY
+}
+
+test_cipher() {
+# $1 - cipher mode
+    KEY="anta-key-cipher"
+    TEST_STR="$(date) Parsec cipher test"
+
+    echo
+    echo "- Creating an AES-128 $1 key"
+    run_cmd $PARSEC_TOOL_CMD create-symmetric-key --key-name $KEY --bits 128 --algorithm $1
+
+    echo
+    echo "- Encrypting and decrypting \"$TEST_STR\" string using the container format"
+    run_cmd $PARSEC_TOOL_CMD encrypt "$TEST_STR" --key-name $KEY >${MY_TMP}/${KEY}.enc
+    run_cmd $PARSEC_TOOL_CMD decrypt $(cat ${MY_TMP}/${KEY}.enc) --key-name $KEY \
+            >${MY_TMP}/${KEY}.enc_str
+    if [ "$(cat ${MY_TMP}/${KEY}.enc_str)" != "$TEST_STR" ]; then
+        echo "Error: The result is different from the initial string"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Encrypting and decrypting \"$TEST_STR\" string with a separate IV"
+    run_cmd $PARSEC_TOOL_CMD encrypt "$TEST_STR" --key-name $KEY --format iv-separate \
+            >${MY_TMP}/${KEY}.enc
+    run_cmd $PARSEC_TOOL_CMD decrypt $(sed -n 2p ${MY_TMP}/${KEY}.enc) --key-name $KEY \
+            --format iv-separate --iv $(sed -n 1p ${MY_TMP}/${KEY}.enc) >${MY_TMP}/${KEY}.enc_str
+    if [ "$(cat ${MY_TMP}/${KEY}.enc_str)" != "$TEST_STR" ]; then
+        echo "Error: The result is different from the initial string"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "AES" $KEY
+}
+
 test_signing() {
 # $1 - key type ("RSA" or "ECC")
     KEY="anta-key-sign"
@@ -264,6 +367,682 @@ test_rsa_key_bits() {
     delete_key "RSA" $KEY
 }
 
+test_rsa_scheme() {
+# $1 - signature scheme ("pkcs1", "pss" or "raw")
+# $2 - hash algorithm, or "" for the default one
+    KEY="anta-key-rsa-scheme"
+    TEST_STR="$(date) Parsec RSA $1 signature test"
+    HASH="${2:-sha256}"
+
+    echo
+    echo "- Creating an RSA $1 signing key"
+    if [ "$2" ]; then
+        run_cmd $PARSEC_TOOL_CMD create-rsa-key --key-name $KEY --for-signing --scheme $1 --hash $2
+    else
+        run_cmd $PARSEC_TOOL_CMD create-rsa-key --key-name $KEY --for-signing --scheme $1
+    fi
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+
+    if [ "$1" = "pss" ]; then
+        SIGOPTS="-sigopt rsa_padding_mode:pss -sigopt rsa_pss_saltlen:digest"
+    else
+        SIGOPTS=""
+    fi
+
+    if [ -s ${MY_TMP}/${KEY}.pem ]; then
+        echo
+        echo "- Signing with the $1 key and verifying the signature with openssl"
+        run_cmd $PARSEC_TOOL_CMD sign "$TEST_STR" --key-name $KEY >${MY_TMP}/${KEY}.sign
+        run_cmd $OPENSSL base64 -d -a -A -in ${MY_TMP}/${KEY}.sign -out ${MY_TMP}/${KEY}.bin
+        printf "$TEST_STR" >${MY_TMP}/${KEY}.test_str
+        run_cmd $OPENSSL dgst -$HASH $SIGOPTS -verify ${MY_TMP}/${KEY}.pem \
+                              -signature ${MY_TMP}/${KEY}.bin ${MY_TMP}/${KEY}.test_str
+
+        echo
+        echo "- Creating a CSR with the $1 key and verifying it with openssl"
+        run_cmd $PARSEC_TOOL_CMD create-csr --cn parallaxsecond.com --key-name $KEY >${MY_TMP}/${KEY}.csr
+        run_cmd $OPENSSL req -noout -verify -in ${MY_TMP}/${KEY}.csr
+    fi
+
+    delete_key "RSA" $KEY
+}
+
+test_rsa_oaep_hash() {
+# $1 - OAEP hash algorithm
+    KEY="anta-key-rsa-oaep-hash"
+    TEST_STR="$(date) Parsec RSA OAEP $1 test"
+
+    echo
+    echo "- Creating an RSA OAEP $1 encryption key"
+    run_cmd $PARSEC_TOOL_CMD create-rsa-key --key-name $KEY --oaep-hash $1
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+
+    if [ -s ${MY_TMP}/${KEY}.pem ]; then
+        echo
+        echo "- Encrypting with openssl and decrypting with Parsec"
+        printf "$TEST_STR" >${MY_TMP}/${KEY}.test_str
+        run_cmd $OPENSSL pkeyutl -encrypt -pubin -inkey ${MY_TMP}/${KEY}.pem \
+                                 -pkeyopt rsa_padding_mode:oaep -pkeyopt rsa_oaep_md:$1 \
+                                 -in ${MY_TMP}/${KEY}.test_str -out ${MY_TMP}/${KEY}.bin
+        run_cmd $OPENSSL base64 -A -in ${MY_TMP}/${KEY}.bin -out ${MY_TMP}/${KEY}.enc
+        run_cmd $PARSEC_TOOL_CMD decrypt $(cat ${MY_TMP}/${KEY}.enc) --key-name $KEY \
+                >${MY_TMP}/${KEY}.enc_str
+        if [ "$(cat ${MY_TMP}/${KEY}.enc_str)" != "$TEST_STR" ]; then
+            echo "Error: The result is different from the initial string"
+            EXIT_CODE=$(($EXIT_CODE+1))
+        fi
+    fi
+
+    delete_key "RSA" $KEY
+}
+
+test_ecc_curve() {
+# $1 - curve name given to create-ecc-key
+# $2 - curve name printed by openssl
+# $3 - hash algorithm of the key policy, or "any"
+    KEY="anta-key-ecc-curve"
+    TEST_STR="$(date) Parsec ECC curve test"
+
+    if [ "$3" = "any" ]; then
+        SIGN_HASH="sha512"
+        SIGN_ARGS="--hash $SIGN_HASH"
+    else
+        SIGN_HASH="$3"
+        SIGN_ARGS=""
+    fi
+
+    echo
+    echo "- Creating an ECC $1 key using $3 hash"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY --curve $1 --hash $3
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    if ! run_cmd $OPENSSL ec -pubin -text -noout -in ${MY_TMP}/${KEY}.pem | grep -q "ASN1 OID: $2"; then
+       echo "Error: create-ecc-key should have produced a $2 key."
+       EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Signing \"$TEST_STR\" string with $SIGN_HASH and verifying it with openssl"
+    run_cmd $PARSEC_TOOL_CMD sign "$TEST_STR" --key-name $KEY $SIGN_ARGS >${MY_TMP}/${KEY}.sign
+    run_cmd $OPENSSL base64 -d -a -A -in ${MY_TMP}/${KEY}.sign -out ${MY_TMP}/${KEY}.bin
+    printf "$TEST_STR" >${MY_TMP}/${KEY}.test_str
+    run_cmd $OPENSSL dgst -$SIGN_HASH -verify ${MY_TMP}/${KEY}.pem \
+                          -signature ${MY_TMP}/${KEY}.bin ${MY_TMP}/${KEY}.test_str
+
+    delete_key "ECC" $KEY
+}
+
+test_key_agreement() {
+# $1 - curve name given to create-ecc-key
+# $2 - openssl genpkey algorithm options for the peer key
+    KEY="anta-key-agreement"
+
+    echo
+    echo "- Creating an ECDH $1 key and a peer key with openssl"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY --curve $1 --ecdh
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    run_cmd $OPENSSL genpkey -algorithm $2 -out ${MY_TMP}/${KEY}.peer.pem
+    run_cmd $OPENSSL pkey -in ${MY_TMP}/${KEY}.peer.pem -pubout -out ${MY_TMP}/${KEY}.peer_pub.pem
+
+    echo
+    echo "- Computing the shared secret with parsec-tool and openssl"
+    run_cmd $PARSEC_TOOL_CMD key-agreement --key-name $KEY --peer-key ${MY_TMP}/${KEY}.peer_pub.pem \
+            >${MY_TMP}/${KEY}.secret
+    run_cmd $OPENSSL pkeyutl -derive -inkey ${MY_TMP}/${KEY}.peer.pem -peerkey ${MY_TMP}/${KEY}.pem \
+                             -out ${MY_TMP}/${KEY}.bin
+    run_cmd $OPENSSL base64 -A -in ${MY_TMP}/${KEY}.bin -out ${MY_TMP}/${KEY}.openssl_secret
+    echo >>${MY_TMP}/${KEY}.openssl_secret
+    if ! cmp -s ${MY_TMP}/${KEY}.secret ${MY_TMP}/${KEY}.openssl_secret; then
+        echo "Error: The shared secrets computed by parsec-tool and openssl differ"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "ECC" $KEY
+}
+
+test_import_key() {
+# $1 - key type ("RSA", "ECC" or "X25519")
+    KEY="anta-key-import"
+    TEST_STR="$(date) Parsec import test"
+
+    if [ "$1" = "X25519" ]; then
+        echo
+        echo "- Creating an X25519 key with openssl and importing its public key into Parsec"
+        run_cmd $OPENSSL genpkey -algorithm X25519 -out ${MY_TMP}/${KEY}.priv.pem
+        run_cmd $OPENSSL pkey -in ${MY_TMP}/${KEY}.priv.pem -pubout -out ${MY_TMP}/${KEY}.pub.pem
+        run_cmd $PARSEC_TOOL_CMD import-key --key-name $KEY --input-file ${MY_TMP}/${KEY}.pub.pem
+        run_cmd $PARSEC_TOOL_CMD key-info --key-name $KEY >${MY_TMP}/${KEY}.info
+        if ! grep -q "ecdh" ${MY_TMP}/${KEY}.info; then
+            echo "Error: The imported X25519 key is not an ECDH key"
+            EXIT_CODE=$(($EXIT_CODE+1))
+        fi
+
+        run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+        if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.pub.pem; then
+            echo "Error: The exported public key is different from the imported one"
+            EXIT_CODE=$(($EXIT_CODE+1))
+        fi
+
+        delete_key $1 $KEY
+        return
+    fi
+
+    echo
+    echo "- Creating an $1 key with openssl and importing it into Parsec"
+    if [ "$1" = "RSA" ]; then
+        run_cmd $OPENSSL genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:${RSA_KEY_SIZE:-2048} \
+                                 -out ${MY_TMP}/${KEY}.priv.pem
+        EXTRA_IMPORT_KEY_ARGS="--for-signing"
+    else
+        run_cmd $OPENSSL genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 \
+                                 -out ${MY_TMP}/${KEY}.priv.pem
+        EXTRA_IMPORT_KEY_ARGS=""
+    fi
+    run_cmd $OPENSSL pkey -in ${MY_TMP}/${KEY}.priv.pem -pubout -out ${MY_TMP}/${KEY}.pub.pem
+    run_cmd $PARSEC_TOOL_CMD import-key --key-name $KEY --input-file ${MY_TMP}/${KEY}.priv.pem \
+                                        $EXTRA_IMPORT_KEY_ARGS
+
+    echo
+    echo "- Checking that the public key exported by Parsec matches the one from openssl"
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.pub.pem; then
+        echo "Error: The exported public key is different from the imported one"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Signing \"$TEST_STR\" string using the imported $1 key and verifying it with openssl"
+    run_cmd $PARSEC_TOOL_CMD sign "$TEST_STR" --key-name $KEY >${MY_TMP}/${KEY}.sign
+    run_cmd $OPENSSL base64 -d -a -A -in ${MY_TMP}/${KEY}.sign -out ${MY_TMP}/${KEY}.bin
+    printf "$TEST_STR" >${MY_TMP}/${KEY}.test_str
+    run_cmd $OPENSSL dgst -sha256 -verify ${MY_TMP}/${KEY}.pub.pem \
+                          -signature ${MY_TMP}/${KEY}.bin ${MY_TMP}/${KEY}.test_str
+
+    delete_key $1 $KEY
+}
+
+test_import_pkcs12() {
+    KEY="anta-key-pkcs12"
+
+    echo
+    echo "- Importing an ECC key and its certificate chain from a PKCS#12 file"
+    run_cmd $OPENSSL req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes -subj "/CN=Anta CA" \
+                         -keyout ${MY_TMP}/${KEY}.ca.key -out ${MY_TMP}/${KEY}.ca.pem
+    run_cmd $OPENSSL req -newkey ec -pkeyopt ec_paramgen_curve:P-256 -nodes -subj "/CN=Anta" \
+                         -keyout ${MY_TMP}/${KEY}.priv.pem -out ${MY_TMP}/${KEY}.csr
+    run_cmd $OPENSSL x509 -req -in ${MY_TMP}/${KEY}.csr -CA ${MY_TMP}/${KEY}.ca.pem \
+                          -CAkey ${MY_TMP}/${KEY}.ca.key -CAcreateserial -out ${MY_TMP}/${KEY}.crt
+    run_cmd $OPENSSL pkcs12 -export -inkey ${MY_TMP}/${KEY}.priv.pem -in ${MY_TMP}/${KEY}.crt \
+                            -certfile ${MY_TMP}/${KEY}.ca.pem -passout pass:anta -out ${MY_TMP}/${KEY}.p12
+    run_cmd $OPENSSL pkey -in ${MY_TMP}/${KEY}.priv.pem -pubout -out ${MY_TMP}/${KEY}.pub.pem
+    run_cmd $PARSEC_TOOL_CMD import-pkcs12 --key-name $KEY --input-file ${MY_TMP}/${KEY}.p12 \
+                                           --password anta --cert-dir ${MY_TMP}/certs
+
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.pub.pem; then
+        echo "Error: The imported public key is different from the one of the PKCS#12 file"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if ! cmp -s ${MY_TMP}/certs/${KEY}.pem ${MY_TMP}/${KEY}.crt \
+            || ! cmp -s ${MY_TMP}/certs/${KEY}.chain.pem ${MY_TMP}/${KEY}.ca.pem; then
+        echo "Error: The stored certificates are different from the ones of the PKCS#12 file"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "ECC" $KEY
+    rm -rf ${MY_TMP}/certs
+}
+
+test_create_key() {
+    KEY="anta-key-create"
+    TEST_STR="$(date) Parsec create-key test"
+
+    echo
+    echo "- Creating an exportable ECC P-384 signing key from a key template"
+    cat >${MY_TMP}/${KEY}.toml <<EOF
+type = "ecc-key-pair(secp-r1)"
+bits = 384
+usage = ["sign", "verify"]
+algorithm = "ecdsa(sha384)"
+EOF
+    run_cmd $PARSEC_TOOL_CMD create-key --key-name $KEY --template ${MY_TMP}/${KEY}.toml --export
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+
+    # If the key was successfully created and exported
+    if [ -s ${MY_TMP}/${KEY}.pem ]; then
+        echo
+        echo "- Signing \"$TEST_STR\" string using the created key and verifying it with openssl"
+        run_cmd $PARSEC_TOOL_CMD sign "$TEST_STR" --key-name $KEY >${MY_TMP}/${KEY}.sign
+        run_cmd $OPENSSL base64 -d -a -A -in ${MY_TMP}/${KEY}.sign -out ${MY_TMP}/${KEY}.bin
+        printf "$TEST_STR" >${MY_TMP}/${KEY}.test_str
+        run_cmd $OPENSSL dgst -sha384 -verify ${MY_TMP}/${KEY}.pem \
+                              -signature ${MY_TMP}/${KEY}.bin ${MY_TMP}/${KEY}.test_str
+
+        echo
+        echo "- Exporting the private key and checking it matches the public key"
+        run_cmd $PARSEC_TOOL_CMD export-key --key-name $KEY >${MY_TMP}/${KEY}.priv.pem
+        run_cmd $OPENSSL pkey -in ${MY_TMP}/${KEY}.priv.pem -pubout -out ${MY_TMP}/${KEY}.pub.pem
+        if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.pub.pem; then
+            echo "Error: The exported private key does not match the public key"
+            EXIT_CODE=$(($EXIT_CODE+1))
+        fi
+
+        run_cmd $PARSEC_TOOL_CMD export-key --key-name $KEY --password "parsec" \
+                >${MY_TMP}/${KEY}.enc.pem
+        run_cmd $OPENSSL pkey -in ${MY_TMP}/${KEY}.enc.pem -passin pass:parsec -pubout \
+                              -out ${MY_TMP}/${KEY}.pub.pem
+        if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.pub.pem; then
+            echo "Error: The encrypted private key does not match the public key"
+            EXIT_CODE=$(($EXIT_CODE+1))
+        fi
+    fi
+
+    delete_key "ECC" $KEY
+}
+
+test_key_info() {
+    KEY="anta-key-info"
+
+    create_key "ECC" $KEY
+
+    echo
+    echo "- Checking the information shown about the key"
+    run_cmd $PARSEC_TOOL_CMD key-info --key-name $KEY >${MY_TMP}/${KEY}.info
+    debug cat ${MY_TMP}/${KEY}.info
+    if ! grep -q "Permitted algorithm: ecdsa(sha256)" ${MY_TMP}/${KEY}.info ||
+       ! grep -q "Usable for: .*sign, create-csr" ${MY_TMP}/${KEY}.info; then
+        echo "Error: key-info does not show the policy of the key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    FINGERPRINT=$($OPENSSL pkey -pubin -in ${MY_TMP}/${KEY}.pem -outform DER | $OPENSSL dgst -sha256 -r | cut -d' ' -f1)
+    if ! grep -q "SPKI SHA-256: *$FINGERPRINT" ${MY_TMP}/${KEY}.info; then
+        echo "Error: key-info does not show the fingerprint of the public key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "ECC" $KEY
+}
+
+test_batch() {
+    KEY="anta-key-batch"
+    TEST_STR="$(date) Parsec batch test"
+
+    echo
+    echo "- Creating a volatile ECC key and signing \"$TEST_STR\" with it in a batch session"
+    cat >${MY_TMP}/${KEY}.batch <<EOF
+# The volatile key only lives within this session
+create-ecc-key --key-name $KEY --volatile
+export-public-key --key-name $KEY
+sign --key-name $KEY "$TEST_STR"
+delete-key --key-name $KEY
+EOF
+    run_cmd $PARSEC_TOOL_CMD batch --input-file ${MY_TMP}/${KEY}.batch >${MY_TMP}/${KEY}.out
+    debug cat ${MY_TMP}/${KEY}.out
+
+    sed -n '/BEGIN PUBLIC KEY/,/END PUBLIC KEY/p' ${MY_TMP}/${KEY}.out >${MY_TMP}/${KEY}.pem
+    tail -n 1 ${MY_TMP}/${KEY}.out >${MY_TMP}/${KEY}.sign
+    run_cmd $OPENSSL base64 -d -a -A -in ${MY_TMP}/${KEY}.sign -out ${MY_TMP}/${KEY}.bin
+    printf "$TEST_STR" >${MY_TMP}/${KEY}.test_str
+    run_cmd $OPENSSL dgst -sha256 -verify ${MY_TMP}/${KEY}.pem \
+                          -signature ${MY_TMP}/${KEY}.bin ${MY_TMP}/${KEY}.test_str
+
+    rm -f ${MY_TMP}/${KEY}.*
+}
+
+test_backup() {
+    KEY="anta-key-backup"
+
+    echo
+    echo "- Creating an exportable ECC key and backing it up"
+    run_cmd $PARSEC_TOOL_CMD create-key --key-name $KEY --type "ecc-key-pair(secp-r1)" --sign --verify \
+            --export --algorithm "ecdsa(sha256)"
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    echo "parsec" >${MY_TMP}/${KEY}.pass
+    run_cmd $PARSEC_TOOL_CMD backup --key-name $KEY --password-file ${MY_TMP}/${KEY}.pass \
+            --output-file ${MY_TMP}/${KEY}.backup
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name $KEY
+
+    echo
+    echo "- Restoring the key and checking its public part"
+    run_cmd $PARSEC_TOOL_CMD restore --input-file ${MY_TMP}/${KEY}.backup --password "parsec"
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.restored.pem
+    if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.restored.pem; then
+        echo "Error: The restored key is different from the original key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Checking that restoring over an existing key fails"
+    if $PARSEC_TOOL_CMD restore --input-file ${MY_TMP}/${KEY}.backup --password "parsec" >/dev/null 2>&1; then
+        echo "Error: Restoring a key which already exists should fail"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "ECC" $KEY
+}
+
+test_delete_keys() {
+    KEY="anta-key-bulk"
+
+    echo
+    echo "- Creating keys and deleting them by pattern"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name ${KEY}-1
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name ${KEY}-2
+    run_cmd $PARSEC_TOOL_CMD delete-keys "${KEY}-*" --dry-run
+    if ! run_cmd $PARSEC_TOOL_CMD list-keys | grep -q "${KEY}-2"; then
+        echo "Error: A dry run should not delete keys"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    # The standard input is not a terminal, so confirmation can not be asked.
+    if echo "y" | $PARSEC_TOOL_CMD delete-keys "${KEY}-*" >/dev/null 2>&1 \
+            || ! run_cmd $PARSEC_TOOL_CMD list-keys | grep -q "${KEY}-1"; then
+        echo "Error: Keys should not be deleted without confirmation"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD delete-keys "${KEY}-*" --type ecc-key-pair --yes
+    if run_cmd $PARSEC_TOOL_CMD list-keys | grep -q "${KEY}-"; then
+        echo "Error: The keys matching the pattern were not deleted"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+}
+
+test_keys_manifest() {
+    KEY="anta-key-manifest"
+
+    cat >${MY_TMP}/${KEY}.toml <<EOF
+[[keys]]
+name = "${KEY}"
+type = "ecc-key-pair(secp-r1)"
+bits = 256
+usage = ["sign", "verify"]
+algorithm = "ecdsa(sha256)"
+EOF
+
+    echo
+    echo "- Applying a key manifest twice"
+    if ! run_cmd $PARSEC_TOOL_CMD keys plan --manifest ${MY_TMP}/${KEY}.toml | grep -q "^+ ${KEY} "; then
+        echo "Error: The plan should create ${KEY}"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD keys apply --manifest ${MY_TMP}/${KEY}.toml
+    if run_cmd $PARSEC_TOOL_CMD keys plan --manifest ${MY_TMP}/${KEY}.toml | grep -q "${KEY}"; then
+        echo "Error: The plan should not change ${KEY} once applied"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD keys apply --manifest ${MY_TMP}/${KEY}.toml
+
+    echo
+    echo "- Checking that a key differing from the manifest is reported"
+    sed -i 's/ecdsa(sha256)/ecdsa(sha384)/' ${MY_TMP}/${KEY}.toml
+    if ! run_cmd $PARSEC_TOOL_CMD keys plan --manifest ${MY_TMP}/${KEY}.toml | grep -q "^~ ${KEY} "; then
+        echo "Error: The plan should report that ${KEY} differs from the manifest"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if $PARSEC_TOOL_CMD keys apply --manifest ${MY_TMP}/${KEY}.toml >/dev/null 2>&1; then
+        echo "Error: Applying a manifest with differing keys should fail"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    rm -f ${MY_TMP}/${KEY}.toml
+    delete_key "ECC" $KEY
+}
+
+test_rotate_key() {
+    KEY="anta-key-rotate"
+    TEST_STR="$(date) Parsec key rotation"
+    # Keep the aliases of the tests apart from the ones of the user
+    export XDG_CONFIG_HOME=${MY_TMP}/config
+
+    echo
+    echo "- Rotating an AES key and decrypting data encrypted with its first version"
+    run_cmd $PARSEC_TOOL_CMD create-symmetric-key --key-name $KEY
+    run_cmd $PARSEC_TOOL_CMD encrypt --key-name $KEY "$TEST_STR" >${MY_TMP}/${KEY}.enc
+    run_cmd $PARSEC_TOOL_CMD rotate-key --key-name $KEY --retention 2
+    if ! run_cmd $PARSEC_TOOL_CMD list-keys | grep -q "${KEY}.v2"; then
+        echo "Error: ${KEY}.v2 was not created"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if [ "$(run_cmd $PARSEC_TOOL_CMD decrypt --key-name $KEY $(cat ${MY_TMP}/${KEY}.enc))" != "$TEST_STR" ]; then
+        echo "Error: Data encrypted with the first version of the key was not decrypted"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Rotating it again with a retention of 2 versions"
+    run_cmd $PARSEC_TOOL_CMD rotate-key --key-name $KEY
+    if run_cmd $PARSEC_TOOL_CMD list-keys | grep -q "$KEY "; then
+        echo "Error: The first version of the key was not deleted"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD encrypt --key-name $KEY "$TEST_STR" >${MY_TMP}/${KEY}.enc
+    if [ "$(run_cmd $PARSEC_TOOL_CMD decrypt --key-name ${KEY}.v3 $(cat ${MY_TMP}/${KEY}.enc))" != "$TEST_STR" ]; then
+        echo "Error: The alias does not refer to the current version of the key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name ${KEY}.v2
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name ${KEY}.v3
+    rm -rf ${MY_TMP}/${KEY}.enc ${XDG_CONFIG_HOME}
+    unset XDG_CONFIG_HOME
+}
+
+test_key_label() {
+    KEY="anta-key-label"
+    export XDG_CONFIG_HOME=${MY_TMP}/config
+
+    echo
+    echo "- Setting metadata of a key and listing keys by label"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY
+    run_cmd $PARSEC_TOOL_CMD key-label set --key-name $KEY --label environment=test \
+            --owner "parsec-tool tests" --not-after 2000-01-01
+    if ! run_cmd $PARSEC_TOOL_CMD key-label get --key-name $KEY | grep -q "^Owner: parsec-tool tests$"; then
+        echo "Error: The owner of the key was not set"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if ! run_cmd $PARSEC_TOOL_CMD list-keys --label environment=test --expires-before 2000-01-02 | grep -q "$KEY"; then
+        echo "Error: The key was not listed with its label"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD key-label rm --key-name $KEY --label environment
+    if run_cmd $PARSEC_TOOL_CMD list-keys --label environment | grep -q "$KEY"; then
+        echo "Error: The label of the key was not removed"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    echo
+    echo "- Checking that deleting the key removes its metadata"
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name $KEY
+    if grep -q "$KEY" ${XDG_CONFIG_HOME}/parsec-tool/metadata.toml; then
+        echo "Error: The metadata of the deleted key was not removed"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    rm -rf ${XDG_CONFIG_HOME}
+    unset XDG_CONFIG_HOME
+}
+
+test_inventory() {
+    KEY="anta-key-inventory"
+
+    echo
+    echo "- Reporting an exportable ECC key in the inventory"
+    run_cmd $PARSEC_TOOL_CMD create-key --key-name $KEY --type "ecc-key-pair(secp-r1)" --sign --verify \
+            --export --algorithm "ecdsa(sha256)"
+    run_cmd $PARSEC_TOOL_CMD inventory --output-file ${MY_TMP}/${KEY}.csv
+    debug cat ${MY_TMP}/${KEY}.csv
+    if ! grep ",${KEY}," ${MY_TMP}/${KEY}.csv | grep -q "exportable-key-pair:low"; then
+        echo "Error: The exportable key was not reported"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    $PARSEC_TOOL_CMD inventory --fail-on low >/dev/null 2>&1
+    if [ $? -ne 3 ]; then
+        echo "Error: The inventory should exit with 3 with findings above the threshold"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    rm -f ${MY_TMP}/${KEY}.csv
+    delete_key "ECC" $KEY
+}
+
+test_list_keys() {
+    KEY="anta-key-list"
+
+    echo
+    echo "- Listing keys with filters"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name ${KEY}-ecc
+    run_cmd $PARSEC_TOOL_CMD create-symmetric-key --key-name ${KEY}-aes
+    if [ "$(run_cmd $PARSEC_TOOL_CMD list-keys --name "${KEY}-*" --type aes --names-only)" != "${KEY}-aes" ]; then
+        echo "Error: Only ${KEY}-aes should be listed"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if [ "$(run_cmd $PARSEC_TOOL_CMD list-keys --name "${KEY}-*" --usage sign --names-only)" != "${KEY}-ecc" ]; then
+        echo "Error: Only ${KEY}-ecc should be listed"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if [ "$(run_cmd $PARSEC_TOOL_CMD list-keys --name "${KEY}-*" --sort name --names-only | head -n 1)" != "${KEY}-aes" ]; then
+        echo "Error: The keys are not sorted by name"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD list-keys --name "${KEY}-*" --long
+
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name ${KEY}-ecc
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name ${KEY}-aes
+}
+
+test_fingerprint() {
+    KEY="anta-key-fingerprint"
+
+    echo
+    echo "- Checking the SPKI fingerprint of an ECC key against OpenSSL"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    run_cmd $PARSEC_TOOL_CMD fingerprint --key-name $KEY --host example.com
+    expected=$($OPENSSL pkey -pubin -in ${MY_TMP}/${KEY}.pem -outform DER | $OPENSSL dgst -sha256 -r | cut -d ' ' -f 1)
+    if [ "$(run_cmd $PARSEC_TOOL_CMD fingerprint --key-name $KEY --format spki-sha256)" != "$expected" ]; then
+        echo "Error: The SPKI fingerprint is different from the one computed by OpenSSL"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if ! run_cmd $PARSEC_TOOL_CMD list-keys --name $KEY --long --fingerprint tlsa | grep -q "TLSA 3 1 1 $expected"; then
+        echo "Error: The TLSA record is not listed"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "ECC" $KEY
+}
+
+test_key_exists() {
+    KEY="anta-key-exists"
+
+    echo
+    echo "- Checking the exit codes of key-exists"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY
+    $PARSEC_TOOL_CMD key-exists --key-name $KEY --expect-type ecc-key-pair
+    if [ $? -ne 0 ]; then
+        echo "Error: key-exists should succeed for an existing key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    $PARSEC_TOOL_CMD key-exists --key-name ${KEY}-absent
+    if [ $? -ne 1 ]; then
+        echo "Error: key-exists should exit with 1 for an absent key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    $PARSEC_TOOL_CMD key-exists --key-name $KEY --expect-type rsa-key-pair
+    if [ $? -ne 2 ]; then
+        echo "Error: key-exists should exit with 2 for a key of another type"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    $PARSEC_TOOL_CMD key-exists --key-name $KEY --expect-algorithm "not-an-algorithm"
+    if [ $? -ne 4 ]; then
+        echo "Error: key-exists should exit with 4 when the key can not be checked"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    delete_key "ECC" $KEY
+}
+
+test_key_uri() {
+# $1 - provider ID
+    KEY="anta-key-uri"
+    KEY_URI="parsec:provider-id=$1;key=$KEY"
+
+    echo
+    echo "- Using the key URI \"$KEY_URI\" without the provider option"
+    run_cmd $PARSEC_TOOL create-ecc-key --key-name "$KEY_URI"
+    run_cmd $PARSEC_TOOL_CMD key-exists --key-name $KEY --expect-type ecc-key-pair
+    run_cmd $PARSEC_TOOL sign --key-name "$KEY_URI" "$(date)" >/dev/null
+    run_cmd $PARSEC_TOOL delete-key --key-name "$KEY_URI"
+    if $PARSEC_TOOL_CMD key-exists --key-name $KEY; then
+        echo "Error: The key should have been deleted through its URI"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+}
+
+test_guardrail_policy() {
+# $1 - provider ID
+    KEY="anta-key-guardrail"
+    PROTECTED_KEY="anta-key-protected"
+    OTHER_PROVIDER=$(( $1 == 1 ? 3 : 1 ))
+
+    echo
+    echo "- Checking the guardrail policy"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $PROTECTED_KEY
+    cat >${MY_TMP}/policy.toml <<EOF
+protected-keys = ["anta-key-protected*"]
+require-yes = true
+allowed-providers = [$1]
+EOF
+    export PARSEC_TOOL_POLICY=${MY_TMP}/policy.toml
+
+    if $PARSEC_TOOL_CMD delete-key --key-name $PROTECTED_KEY --yes; then
+        echo "Error: A protected key should not be deleted"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    if $PARSEC_TOOL_CMD delete-key --key-name $KEY; then
+        echo "Error: Deleting a key without --yes should fail"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    run_cmd $PARSEC_TOOL_CMD delete-key --key-name $KEY --yes
+    if $PARSEC_TOOL -p $OTHER_PROVIDER create-ecc-key --key-name $KEY; then
+        echo "Error: A provider which is not allowed should not be targeted"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+
+    unset PARSEC_TOOL_POLICY
+    delete_key "ECC" $PROTECTED_KEY
+}
+
+test_migrate_key() {
+# $1 - source provider ID
+# $2 - target provider ID
+    KEY="anta-key-migrate"
+    PARSEC_TOOL_CMD="$PARSEC_TOOL -p $1"
+
+    echo
+    echo "- Migrating an exportable ECC key from provider $1 to provider $2"
+    run_cmd $PARSEC_TOOL_CMD create-key --key-name $KEY --type "ecc-key-pair(secp-r1)" --sign --verify \
+            --export --algorithm "ecdsa(sha256)"
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.pem
+    run_cmd $PARSEC_TOOL migrate-key --key-name "parsec:provider-id=$1;key=$KEY" --to-provider $2 --delete-source
+
+    PARSEC_TOOL_CMD="$PARSEC_TOOL -p $2"
+    run_cmd $PARSEC_TOOL_CMD export-public-key --key-name $KEY >${MY_TMP}/${KEY}.migrated.pem
+    if ! cmp -s ${MY_TMP}/${KEY}.pem ${MY_TMP}/${KEY}.migrated.pem; then
+        echo "Error: The migrated key is different from the original key"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    delete_key "ECC" $KEY
+
+    echo
+    echo "- Checking that a non-exportable key can not be migrated"
+    PARSEC_TOOL_CMD="$PARSEC_TOOL -p $1"
+    run_cmd $PARSEC_TOOL_CMD create-ecc-key --key-name $KEY
+    if $PARSEC_TOOL_CMD migrate-key --key-name $KEY --from-provider $1 --to-provider $2 >/dev/null 2>&1; then
+        echo "Error: Migrating a non-exportable key should fail"
+        EXIT_CODE=$(($EXIT_CODE+1))
+    fi
+    delete_key "ECC" $KEY
+}
+
 PARSEC_TOOL_DEBUG=
 PROVIDER=
 
@@ -350,4 +1129,11 @@ while IFS= read -r prv; do
     fi
 done
 
+# Key migration needs two providers
+if [ -z "$PROVIDER" ] && [ "$(wc -l < ${MY_TMP}/providers.lst)" -ge 2 ]; then
+    from_id=$(($(sed -n 1p ${MY_TMP}/providers.lst | cut -f 2 -d ' ')))
+    to_id=$(($(sed -n 2p ${MY_TMP}/providers.lst | cut -f 2 -d ' ')))
+    test_migrate_key $from_id $to_id
+fi
+
 exit $EXIT_CODE